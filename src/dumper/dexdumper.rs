@@ -1,12 +1,15 @@
 use nix::sys::signal::{kill, Signal};
 use nix::unistd::Pid;
-use proc_maps::MapRange;
 
-use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use byteorder::{ByteOrder, LittleEndian, ReadBytesExt, WriteBytesExt};
 use regex::bytes::Regex;
-use std::cell::RefCell;
-use std::io::{Cursor, Read, Seek, SeekFrom, Write};
-use std::path::Path;
+use std::io::{Cursor, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::utils::{
+    default_reader, parse_proc_maps, read_maps, write_missing_report, MemoryMapping, MemoryReader,
+    MissingRanges, SnapshotReader, SNAPSHOT_MAPS,
+};
 
 // Constants for DEX file structure
 // Author: mrack <https://github.com/mrack>
@@ -27,6 +30,7 @@ pub enum DexDumperError {
     FailedToAttach,
     FailedToDetach,
     FileCreationFailed,
+    InvalidSnapshot(String),
     IoError(std::io::Error),
 }
 
@@ -37,6 +41,7 @@ impl std::fmt::Display for DexDumperError {
             DexDumperError::FailedToAttach => write!(f, "Failed to attach to process"),
             DexDumperError::FailedToDetach => write!(f, "Failed to detach from process"),
             DexDumperError::FileCreationFailed => write!(f, "Failed to create output file"),
+            DexDumperError::InvalidSnapshot(e) => write!(f, "Invalid snapshot: {}", e),
             DexDumperError::IoError(e) => write!(f, "IO error: {}", e),
         }
     }
@@ -52,44 +57,76 @@ impl From<std::io::Error> for DexDumperError {
 
 pub struct DexDumper {
     pid: Pid,
-    mem_fd: RefCell<std::fs::File>,
-    maps: Vec<MapRange>,
+    reader: Box<dyn MemoryReader>,
+    maps: Vec<MemoryMapping>,
+    // 离线快照目录，设置时不向目标进程发送信号
+    snapshot: Option<PathBuf>,
     dex_regex: Regex,
 }
 
 impl DexDumper {
     pub fn new(pid: i32) -> Result<Self, DexDumperError> {
-        let mem_fd = std::fs::File::open(format!("/proc/{}/mem", pid))
-            .map_err(|_| DexDumperError::ProcessNotFound(pid))?;
-
-        let dex_regex =
-            Regex::new(r"\x64\x65\x78\x0a\x30..\x00").expect("Failed to compile DEX regex");
+        if !Path::new(&format!("/proc/{}", pid)).exists() {
+            return Err(DexDumperError::ProcessNotFound(pid));
+        }
+        let reader = default_reader(pid as u32);
 
         Ok(DexDumper {
             pid: Pid::from_raw(pid),
             maps: Vec::new(),
-            mem_fd: RefCell::new(mem_fd),
-            dex_regex,
+            reader,
+            snapshot: None,
+            dex_regex: Self::dex_regex(),
         })
     }
 
+    /// 从write_snapshot写出的快照目录离线搜索DEX
+    pub fn from_snapshot(snapshot: &Path) -> Result<Self, DexDumperError> {
+        let reader = SnapshotReader::new(snapshot)
+            .map_err(|e| DexDumperError::InvalidSnapshot(e.to_string()))?;
+
+        Ok(DexDumper {
+            pid: Pid::from_raw(0),
+            maps: Vec::new(),
+            reader: Box::new(reader),
+            snapshot: Some(snapshot.to_path_buf()),
+            dex_regex: Self::dex_regex(),
+        })
+    }
+
+    fn dex_regex() -> Regex {
+        Regex::new(r"\x64\x65\x78\x0a\x30..\x00").expect("Failed to compile DEX regex")
+    }
+
+    pub fn set_reader(&mut self, reader: Box<dyn MemoryReader>) {
+        self.reader = reader;
+    }
+
     pub fn attach_process(&mut self) -> Result<(), DexDumperError> {
+        if let Some(snapshot) = &self.snapshot {
+            self.maps = read_maps(&snapshot.join(SNAPSHOT_MAPS))
+                .map_err(|e| DexDumperError::InvalidSnapshot(e.to_string()))?;
+            return Ok(());
+        }
+
         kill(self.pid, Signal::SIGSTOP).map_err(|_| DexDumperError::FailedToAttach)?;
 
-        self.maps = proc_maps::get_process_maps(self.pid.as_raw())
+        self.maps = parse_proc_maps(self.pid.as_raw() as u32)
             .map_err(|_| DexDumperError::FailedToAttach)?;
 
         Ok(())
     }
 
     pub fn detach_process(&self) -> Result<(), DexDumperError> {
+        if self.snapshot.is_some() {
+            return Ok(());
+        }
         kill(self.pid, Signal::SIGCONT).map_err(|_| DexDumperError::FailedToDetach)
     }
 
     fn read_dex_header_value(&self, address: usize, offset: u64) -> Option<u32> {
-        let mut cursor = self.mem_fd.borrow_mut();
-        cursor.seek(SeekFrom::Start(address as u64 + offset)).ok()?;
-        cursor.read_u32::<LittleEndian>().ok()
+        let data = self.reader.read_bytes(address as u64 + offset, 4).ok()?;
+        Some(LittleEndian::read_u32(&data))
     }
 
     fn guess_dex_size(&self, dex_header_addr: usize) -> Option<(usize, usize)> {
@@ -142,7 +179,7 @@ impl DexDumper {
 
     fn process_dex_found(&self, out_path: &Path, real_addr: usize) -> Result<(), DexDumperError> {
         if let Some((file_size, actual_size)) = self.guess_dex_size(real_addr) {
-            if let Some(data) = self.read_memory_proc(real_addr, actual_size) {
+            if let Some((data, missing)) = self.read_memory_proc(real_addr, actual_size) {
                 println!(
                     "Found DEX at {:#08x}, file_size: {:#08x}, actual_size: {:#08x}",
                     real_addr, file_size, actual_size
@@ -154,6 +191,7 @@ impl DexDumper {
 
                 file.write_all(&data)?;
                 println!("Saved DEX to: {}", output_path.display());
+                Self::report_missing(&output_path, &missing);
             } else {
                 println!(
                     "Failed to read memory at {:#08x} - {:#08x}",
@@ -168,36 +206,38 @@ impl DexDumper {
     fn process_memory_region(
         &self,
         out_path: &Path,
-        memory_map: &MapRange,
+        memory_map: &MemoryMapping,
     ) -> Result<(), DexDumperError> {
-        if let Some(mem) = self.read_memory_proc(memory_map.start(), memory_map.size()) {
+        let start = memory_map.start as usize;
+        let size = (memory_map.end - memory_map.start) as usize;
+        if let Some((mem, _)) = self.read_memory_proc(start, size) {
             for dex_match in self.dex_regex.find_iter(&mem) {
-                let real_addr = memory_map.start() + dex_match.start();
+                let real_addr = start + dex_match.start();
                 self.process_dex_found(out_path, real_addr)?;
             }
 
             if mem.len() >= 3 && &mem[0..3] != b"dex" {
-                if let Some((file_size, guess_size)) = self.guess_dex_size(memory_map.start()) {
+                if let Some((file_size, guess_size)) = self.guess_dex_size(start) {
                     println!(
                         "No header found, file_size: {:#08x}, guess_size: {:#08x}",
                         file_size, guess_size
                     );
 
-                    if let Some(data) = self.read_memory_proc(memory_map.start(), guess_size) {
+                    if let Some((data, missing)) = self.read_memory_proc(start, guess_size) {
                         if let Some(fixed_dex) = Self::fix_dex_header(&data) {
-                            let output_path =
-                                out_path.join(format!("dex_{:#08x}.dex", memory_map.start()));
+                            let output_path = out_path.join(format!("dex_{:#08x}.dex", start));
                             let mut file = std::fs::File::create(&output_path)
                                 .map_err(|_| DexDumperError::FileCreationFailed)?;
 
                             file.write_all(&fixed_dex)?;
                             println!("Saved fixed DEX to: {}", output_path.display());
+                            Self::report_missing(&output_path, &missing);
                         }
                     } else {
                         println!(
                             "Failed to read memory at {:#08x} - {:#08x}",
-                            memory_map.start(),
-                            memory_map.start() + guess_size
+                            start,
+                            start + guess_size
                         );
                     }
                 }
@@ -214,8 +254,13 @@ impl DexDumper {
         let filtered_maps: Vec<_> = self
             .maps
             .iter()
-            .filter(|m| m.is_read() && m.size() > MIN_MEMORY_SIZE)
-            .filter(|m| !Self::should_skip_memory_region(m.filename()))
+            .filter(|m| {
+                m.permissions.starts_with('r') && (m.end - m.start) as usize > MIN_MEMORY_SIZE
+            })
+            .filter(|m| {
+                let filename = (!m.pathname.is_empty()).then(|| Path::new(&m.pathname));
+                !Self::should_skip_memory_region(filename)
+            })
             .collect();
 
         println!(
@@ -227,8 +272,7 @@ impl DexDumper {
             if let Err(e) = self.process_memory_region(out_path, memory_map) {
                 eprintln!(
                     "Error processing memory region {:#08x}: {}",
-                    memory_map.start(),
-                    e
+                    memory_map.start, e
                 );
             }
         }
@@ -237,19 +281,27 @@ impl DexDumper {
         Ok(())
     }
 
-    fn read_memory_proc(&self, address: usize, size: usize) -> Option<Vec<u8>> {
-        let mut buffer = vec![0u8; size];
-        let mut mem_fd = self.mem_fd.borrow_mut();
-
-        if mem_fd.seek(SeekFrom::Start(address as u64)).is_err() {
+    fn read_memory_proc(&self, address: usize, size: usize) -> Option<(Vec<u8>, MissingRanges)> {
+        let (data, missing) = self.reader.read_tolerant(address as u64, size);
+        let missing_size: u64 = missing.iter().map(|(start, end)| end - start).sum();
+        if size == 0 || missing_size >= size as u64 {
             return None;
         }
+        Some((data, missing))
+    }
 
-        if mem_fd.read_exact(&mut buffer).is_err() {
-            return None;
+    fn report_missing(output_path: &Path, missing: &[(u64, u64)]) {
+        if missing.is_empty() {
+            return;
+        }
+        match write_missing_report(output_path, missing) {
+            Ok(report) => println!(
+                "Zero-filled {} unreadable ranges, report: {}",
+                missing.len(),
+                report.display()
+            ),
+            Err(e) => eprintln!("Failed to write missing range report: {}", e),
         }
-
-        Some(buffer)
     }
 }
 
@@ -0,0 +1,206 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::Read;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use crate::utils::ELF_MAGIC;
+
+const DEX_MAGIC_PREFIX: &[u8] = b"dex\n";
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// 通过/proc/pid/map_files和/proc/pid/fd恢复已删除或memfd中的文件
+pub struct FileDumper {
+    target_pid: u32,
+    output_dir: PathBuf,
+}
+
+struct RecoverCandidate {
+    proc_path: PathBuf,
+    target: String,
+}
+
+impl FileDumper {
+    pub fn new(target_pid: u32, output_dir: PathBuf) -> Self {
+        Self {
+            target_pid,
+            output_dir,
+        }
+    }
+
+    fn is_recoverable(target: &str) -> bool {
+        target.ends_with("(deleted)") || target.contains("memfd:")
+    }
+
+    fn collect_candidates(&self, dir: &str) -> Vec<RecoverCandidate> {
+        let dir_path = format!("/proc/{}/{}", self.target_pid, dir);
+        let entries = match fs::read_dir(&dir_path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("[!] Failed to read {}: {}", dir_path, e);
+                return Vec::new();
+            }
+        };
+
+        entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let target = fs::read_link(entry.path()).ok()?;
+                let target = target.to_string_lossy().into_owned();
+                Self::is_recoverable(&target).then(|| RecoverCandidate {
+                    proc_path: entry.path(),
+                    target,
+                })
+            })
+            .collect()
+    }
+
+    fn file_extension(data: &[u8], target: &str) -> Option<&'static str> {
+        let name = target.trim_end_matches(" (deleted)").to_lowercase();
+        if data.starts_with(ELF_MAGIC) {
+            Some("so")
+        } else if data.starts_with(DEX_MAGIC_PREFIX) {
+            Some("dex")
+        } else if data.starts_with(ZIP_MAGIC) {
+            if name.ends_with(".apk") {
+                Some("apk")
+            } else {
+                Some("jar")
+            }
+        } else {
+            None
+        }
+    }
+
+    fn output_name(target: &str, inode: u64, extension: &str) -> String {
+        let name = target
+            .trim_end_matches(" (deleted)")
+            .rsplit('/')
+            .next()
+            .unwrap_or("file")
+            .replace(
+                |c: char| !c.is_ascii_alphanumeric() && c != '.' && c != '_',
+                "_",
+            );
+        let stem = Path::new(&name)
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or(name);
+
+        format!("{}_{}_recovered.{}", stem, inode, extension)
+    }
+
+    /// 恢复已删除或memfd中的SO、DEX、JAR、APK文件
+    pub fn recover(&self) -> Result<Vec<PathBuf>> {
+        println!("[+] Recovering deleted and memfd files");
+        println!("[+] Target PID: {}", self.target_pid);
+
+        let mut candidates = self.collect_candidates("map_files");
+        candidates.extend(self.collect_candidates("fd"));
+        if candidates.is_empty() {
+            return Err(anyhow!("No deleted or memfd files found"));
+        }
+
+        let mut seen = HashSet::new();
+        let mut recovered = Vec::new();
+
+        for candidate in candidates {
+            let mut file = match File::open(&candidate.proc_path) {
+                Ok(file) => file,
+                Err(e) => {
+                    eprintln!(
+                        "[!] Failed to open {} ({}): {}",
+                        candidate.proc_path.display(),
+                        candidate.target,
+                        e
+                    );
+                    continue;
+                }
+            };
+
+            let metadata = match file.metadata() {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    eprintln!("[!] Failed to stat {}: {}", candidate.target, e);
+                    continue;
+                }
+            };
+            if !metadata.is_file() || !seen.insert((metadata.dev(), metadata.ino())) {
+                continue;
+            }
+
+            let mut magic = [0u8; 4];
+            if file.read_exact(&mut magic).is_err() {
+                continue;
+            }
+            let Some(extension) = Self::file_extension(&magic, &candidate.target) else {
+                println!("[*] Skipping {}: unknown file type", candidate.target);
+                continue;
+            };
+
+            let mut data = Vec::with_capacity(metadata.len() as usize);
+            data.extend_from_slice(&magic);
+            if let Err(e) = file.read_to_end(&mut data) {
+                eprintln!("[!] Failed to read {}: {}", candidate.target, e);
+                continue;
+            }
+
+            let output_path = self.output_dir.join(Self::output_name(
+                &candidate.target,
+                metadata.ino(),
+                extension,
+            ));
+            fs::write(&output_path, &data)?;
+
+            println!(
+                "[+] Recovered {} ({} bytes) to: {}",
+                candidate.target,
+                data.len(),
+                output_path.display()
+            );
+            recovered.push(output_path);
+        }
+
+        println!("[+] Recovered {} files", recovered.len());
+        Ok(recovered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_extension_by_magic() {
+        assert_eq!(
+            FileDumper::file_extension(ELF_MAGIC, "/memfd:jit (deleted)"),
+            Some("so")
+        );
+        assert_eq!(
+            FileDumper::file_extension(b"dex\n035\0", "/data/classes.dex"),
+            Some("dex")
+        );
+        assert_eq!(
+            FileDumper::file_extension(ZIP_MAGIC, "/data/app/Base.APK (deleted)"),
+            Some("apk")
+        );
+        assert_eq!(
+            FileDumper::file_extension(ZIP_MAGIC, "/data/local/tmp/payload.zip"),
+            Some("jar")
+        );
+        assert_eq!(FileDumper::file_extension(b"\0\0\0\0", "/a.so"), None);
+    }
+
+    #[test]
+    fn output_name_sanitizes_target() {
+        assert_eq!(
+            FileDumper::output_name("/data/local/tmp/libpayload.so (deleted)", 42, "so"),
+            "libpayload_42_recovered.so"
+        );
+        assert_eq!(
+            FileDumper::output_name("/memfd:jit-cache (deleted)", 7, "dex"),
+            "memfd_jit_cache_7_recovered.dex"
+        );
+    }
+}
@@ -0,0 +1,300 @@
+use goblin::elf::header::{EM_386, EM_AARCH64, EM_ARM, EM_X86_64};
+
+use crate::utils::{read_u16, read_u32, read_u64};
+
+const ENDBR64: &[u8] = &[0xf3, 0x0f, 0x1e, 0xfa];
+const ENDBR32: &[u8] = &[0xf3, 0x0f, 0x1e, 0xfb];
+const THUMB_NOP: u16 = 0xbf00;
+
+fn sign_extend(value: u64, bits: u32) -> i64 {
+    let shift = 64 - bits;
+    ((value << shift) as i64) >> shift
+}
+
+/// 识别inline hook框架写在函数开头的跳转指令，返回跳转目标
+/// code从函数地址开始读取，address为符号地址，ARM的Thumb函数最低位为1
+pub fn decode_trampoline(machine: u16, code: &[u8], address: u64) -> Option<u64> {
+    match machine {
+        EM_AARCH64 => decode_arm64(code, address),
+        EM_ARM if address & 1 != 0 => decode_thumb(code, address & !1),
+        EM_ARM => decode_arm(code, address),
+        EM_X86_64 | EM_386 => decode_x86(code, address, machine == EM_X86_64),
+        _ => None,
+    }
+}
+
+fn decode_arm64(code: &[u8], pc: u64) -> Option<u64> {
+    let insn = |i: usize| read_u32(code, i * 4);
+    let is_br = |i: usize, reg: u32| {
+        insn(i).is_some_and(|x| x & 0xffff_fc1f == 0xd61f_0000 && (x >> 5) & 0x1f == reg)
+    };
+    let first = insn(0)?;
+
+    // LDR Xn, #imm; BR Xn; .quad target
+    if first & 0xff00_0000 == 0x5800_0000 && is_br(1, first & 0x1f) {
+        let literal = sign_extend(((first >> 5) & 0x7ffff) as u64, 19) * 4;
+        return read_u64(code, usize::try_from(literal).ok()?);
+    }
+
+    // ADRP Xn, page; ADD Xn, Xn, #imm; BR Xn
+    if first & 0x9f00_0000 == 0x9000_0000 {
+        let reg = first & 0x1f;
+        let add = insn(1)?;
+        if add & 0xffc0_0000 == 0x9100_0000
+            && add & 0x1f == reg
+            && (add >> 5) & 0x1f == reg
+            && is_br(2, reg)
+        {
+            let imm = (((first >> 5) & 0x7ffff) << 2 | ((first >> 29) & 3)) as u64;
+            let page = (pc & !0xfff).wrapping_add((sign_extend(imm, 21) << 12) as u64);
+            return Some(page + ((add >> 10) & 0xfff) as u64);
+        }
+    }
+
+    // B imm26
+    if first & 0xfc00_0000 == 0x1400_0000 {
+        return Some(pc.wrapping_add((sign_extend((first & 0x3ff_ffff) as u64, 26) * 4) as u64));
+    }
+
+    None
+}
+
+fn decode_arm(code: &[u8], pc: u64) -> Option<u64> {
+    let first = read_u32(code, 0)?;
+
+    // LDR PC, [PC, #-4]; .word target
+    if first == 0xe51f_f004 {
+        return read_u32(code, 4).map(|target| target as u64);
+    }
+
+    // B imm24
+    if first & 0xff00_0000 == 0xea00_0000 {
+        let offset = sign_extend((first & 0xff_ffff) as u64, 24) * 4;
+        return Some((pc + 8).wrapping_add(offset as u64) & 0xffff_ffff);
+    }
+
+    None
+}
+
+fn decode_thumb(code: &[u8], pc: u64) -> Option<u64> {
+    // 未对齐时hook框架会先填一个NOP
+    let start = if read_u16(code, 0)? == THUMB_NOP {
+        2
+    } else {
+        0
+    };
+    let hw1 = read_u16(code, start)?;
+    let hw2 = read_u16(code, start + 2)?;
+    let insn_addr = pc + start as u64;
+
+    // LDR.W PC, [PC, #0]; .word target
+    if hw1 == 0xf8df && hw2 == 0xf000 {
+        let literal = ((insn_addr + 4) & !3) - pc;
+        return read_u32(code, literal as usize).map(|target| target as u64);
+    }
+
+    // B.W imm24
+    if hw1 & 0xf800 == 0xf000 && hw2 & 0xd000 == 0x9000 {
+        let s = ((hw1 >> 10) & 1) as u64;
+        let i1 = !(((hw2 >> 13) & 1) as u64 ^ s) & 1;
+        let i2 = !(((hw2 >> 11) & 1) as u64 ^ s) & 1;
+        let imm = (s << 24)
+            | (i1 << 23)
+            | (i2 << 22)
+            | (((hw1 & 0x3ff) as u64) << 12)
+            | (((hw2 & 0x7ff) as u64) << 1);
+        let target = (insn_addr + 4).wrapping_add(sign_extend(imm, 25) as u64);
+        return Some((target & 0xffff_ffff) | 1);
+    }
+
+    None
+}
+
+fn decode_x86(code: &[u8], ip: u64, is_64: bool) -> Option<u64> {
+    let start = if code.starts_with(ENDBR64) || code.starts_with(ENDBR32) {
+        4
+    } else {
+        0
+    };
+    let code = code.get(start..)?;
+    let ip = ip + start as u64;
+    let mask = if is_64 { u64::MAX } else { u32::MAX as u64 };
+    let rel32 = |offset: usize| read_u32(code, offset).map(|v| sign_extend(v as u64, 32) as u64);
+
+    match code {
+        // JMP rel32
+        [0xe9, ..] => Some(ip.wrapping_add(5).wrapping_add(rel32(1)?) & mask),
+        // JMP [RIP+0]; .quad target
+        [0xff, 0x25, 0, 0, 0, 0, ..] if is_64 => read_u64(code, 6),
+        // MOVABS RAX/R11, imm64; JMP RAX/R11
+        [0x48, 0xb8, ..] if is_64 && code.get(10..12) == Some(&[0xff, 0xe0]) => read_u64(code, 2),
+        [0x49, 0xbb, ..] if is_64 && code.get(10..13) == Some(&[0x41, 0xff, 0xe3]) => {
+            read_u64(code, 2)
+        }
+        // MOV EAX, imm32; JMP EAX
+        [0xb8, ..] if !is_64 && code.get(5..7) == Some(&[0xff, 0xe0]) => {
+            read_u32(code, 1).map(|v| v as u64)
+        }
+        // PUSH imm32; RET
+        [0x68, _, _, _, _, 0xc3, ..] => Some(rel32(1)? & mask),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn le32(words: &[u32]) -> Vec<u8> {
+        words.iter().flat_map(|w| w.to_le_bytes()).collect()
+    }
+
+    fn le16(halfwords: &[u16]) -> Vec<u8> {
+        halfwords.iter().flat_map(|h| h.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn decode_trampolines() {
+        let target: u64 = 0x7f00_1234_5678;
+        let cases = vec![
+            (
+                "arm64 ldr x17; br x17",
+                EM_AARCH64,
+                [
+                    le32(&[0x5800_0051, 0xd61f_0220]),
+                    target.to_le_bytes().to_vec(),
+                ]
+                .concat(),
+                0x1000,
+                Some(target),
+            ),
+            (
+                "arm64 adrp; add; br",
+                EM_AARCH64,
+                le32(&[0xb000_0030, 0x9104_8e10, 0xd61f_0200]),
+                0x1000_0010,
+                Some(0x1000_5123),
+            ),
+            (
+                "arm64 b",
+                EM_AARCH64,
+                le32(&[0x1400_0040]),
+                0x2000,
+                Some(0x2100),
+            ),
+            (
+                "arm64 b back",
+                EM_AARCH64,
+                le32(&[0x17ff_fffc]),
+                0x2000,
+                Some(0x1ff0),
+            ),
+            ("arm64 stp", EM_AARCH64, le32(&[0xa9bf_7bfd]), 0x2000, None),
+            (
+                "arm ldr pc",
+                EM_ARM,
+                le32(&[0xe51f_f004, 0x1234_5678]),
+                0x8000,
+                Some(0x1234_5678),
+            ),
+            ("arm b", EM_ARM, le32(&[0xea00_0010]), 0x8000, Some(0x8048)),
+            (
+                "thumb ldr.w pc",
+                EM_ARM,
+                [le16(&[0xf8df, 0xf000]), le32(&[0x4000_1235])].concat(),
+                0x9001,
+                Some(0x4000_1235),
+            ),
+            (
+                "thumb nop; ldr.w pc",
+                EM_ARM,
+                [le16(&[THUMB_NOP, 0xf8df, 0xf000]), le32(&[0x4000_1235])].concat(),
+                0x9003,
+                Some(0x4000_1235),
+            ),
+            (
+                "thumb nop; b.w",
+                EM_ARM,
+                le16(&[THUMB_NOP, 0xf001, 0xb800]),
+                0xa003,
+                Some(0xb009),
+            ),
+            ("thumb push", EM_ARM, le16(&[0xb580, 0xaf00]), 0xa001, None),
+            (
+                "x86_64 jmp rel32",
+                EM_X86_64,
+                [vec![0xe9], le32(&[0x100])].concat(),
+                0x40_1000,
+                Some(0x40_1105),
+            ),
+            (
+                "x86_64 endbr64; jmp rel32",
+                EM_X86_64,
+                [ENDBR64.to_vec(), vec![0xe9], le32(&[0xffff_fff0])].concat(),
+                0x40_1000,
+                Some(0x40_0ff9),
+            ),
+            (
+                "x86_64 jmp [rip]",
+                EM_X86_64,
+                [vec![0xff, 0x25, 0, 0, 0, 0], target.to_le_bytes().to_vec()].concat(),
+                0x40_1000,
+                Some(target),
+            ),
+            (
+                "x86_64 movabs rax; jmp rax",
+                EM_X86_64,
+                [
+                    vec![0x48, 0xb8],
+                    target.to_le_bytes().to_vec(),
+                    vec![0xff, 0xe0],
+                ]
+                .concat(),
+                0x40_1000,
+                Some(target),
+            ),
+            (
+                "x86_64 movabs r11; jmp r11",
+                EM_X86_64,
+                [
+                    vec![0x49, 0xbb],
+                    target.to_le_bytes().to_vec(),
+                    vec![0x41, 0xff, 0xe3],
+                ]
+                .concat(),
+                0x40_1000,
+                Some(target),
+            ),
+            (
+                "x86 push; ret",
+                EM_386,
+                [vec![0x68], le32(&[0x0804_9000]), vec![0xc3]].concat(),
+                0x0804_8000,
+                Some(0x0804_9000),
+            ),
+            (
+                "x86 mov eax; jmp eax",
+                EM_386,
+                [vec![0xb8], le32(&[0x0804_9000]), vec![0xff, 0xe0]].concat(),
+                0x0804_8000,
+                Some(0x0804_9000),
+            ),
+            (
+                "x86_64 push rbp",
+                EM_X86_64,
+                vec![0x55, 0x48, 0x89, 0xe5],
+                0x40_1000,
+                None,
+            ),
+        ];
+
+        for (name, machine, code, address, expected) in cases {
+            assert_eq!(
+                decode_trampoline(machine, &code, address),
+                expected,
+                "{}",
+                name
+            );
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use anyhow::Result;
+use goblin::elf::program_header::PF_X;
+use regex::Regex;
+
+use crate::utils::{min_load_vaddr, parse_program_headers, read_cstr, ElfHeader, ProgramHeader};
+
+const JNI_SIGNATURE_PATTERN: &str =
+    r"^\((\[*([ZBCSIJFD]|L[^;()\[]+;))*\)(V|\[*([ZBCSIJFD]|L[^;()\[]+;))$";
+
+/// 从dump中恢复的RegisterNatives注册表项JNINativeMethod { name, signature, fnPtr }
+#[derive(Debug, Clone)]
+pub struct JniMethod {
+    pub table: u64,
+    pub name: String,
+    pub signature: String,
+    pub function: u64,
+}
+
+fn is_method_name(name: &str) -> bool {
+    name.len() <= 256
+        && name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+/// 扫描不可执行的PT_LOAD段，查找{name, signature, fnPtr}三元组
+/// dump中的指针为运行时地址，table和function为相对min_vaddr的虚拟地址
+pub fn scan_native_methods(dump: &[u8], base: u64) -> Result<Vec<JniMethod>> {
+    let header = ElfHeader::parse(dump)?;
+    let class = header.class;
+    let phdrs = parse_program_headers(dump, &header)?;
+    let min_vaddr = min_load_vaddr(&phdrs);
+    let signature_re = Regex::new(JNI_SIGNATURE_PATTERN)?;
+    let ptr_size = class.ptr_size();
+
+    let to_off = |ptr: u64| {
+        ptr.checked_sub(base)
+            .map(|o| o as usize)
+            .filter(|o| *o < dump.len())
+    };
+    let segment_range = |p: &ProgramHeader| {
+        let start = (p.p_vaddr - min_vaddr) as usize;
+        start..start.saturating_add(p.p_memsz as usize).min(dump.len())
+    };
+    let exec: Vec<_> = phdrs
+        .iter()
+        .filter(|p| p.is_load() && p.p_flags & PF_X != 0)
+        .map(segment_range)
+        .collect();
+
+    let mut methods = Vec::new();
+    for data in phdrs
+        .iter()
+        .filter(|p| p.is_load() && p.p_flags & PF_X == 0)
+        .map(segment_range)
+    {
+        let start = data.start.next_multiple_of(ptr_size);
+        for offset in (start..=data.end.saturating_sub(ptr_size * 3)).step_by(ptr_size) {
+            let (Some(name_ptr), Some(signature_ptr), Some(function)) = (
+                class.read_word(dump, offset),
+                class.read_word(dump, offset + ptr_size),
+                class.read_word(dump, offset + ptr_size * 2),
+            ) else {
+                continue;
+            };
+
+            // ARM的Thumb函数指针最低位为1
+            let Some(function_off) = to_off(function & !1) else {
+                continue;
+            };
+            if !exec.iter().any(|range| range.contains(&function_off)) {
+                continue;
+            }
+            let Some(signature) = to_off(signature_ptr).and_then(|o| read_cstr(dump, o)) else {
+                continue;
+            };
+            if !signature_re.is_match(&signature) {
+                continue;
+            }
+            let Some(name) = to_off(name_ptr).and_then(|o| read_cstr(dump, o)) else {
+                continue;
+            };
+            if !is_method_name(&name) {
+                continue;
+            }
+
+            methods.push(JniMethod {
+                table: min_vaddr + offset as u64,
+                name,
+                signature,
+                function: min_vaddr + (function - base),
+            });
+        }
+    }
+
+    Ok(methods)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::ElfClass;
+
+    const FIXTURE: &[u8] = include_bytes!("../../tests/fixtures/libfixture_x86_64_dump.bin");
+    const FIXTURE_BASE: u64 = 0x7f64_1345_c000;
+
+    #[test]
+    fn scan_native_methods_finds_triple() {
+        // 只读段0x2040处放一项JNINativeMethod，函数指向.text
+        let class = ElfClass::Elf64;
+        let mut dump = FIXTURE.to_vec();
+        dump[0x3e0..0x3eb].copy_from_slice(b"nativeInit\0");
+        dump[0x3f0..0x3f5].copy_from_slice(b"(I)V\0");
+        for (i, ptr) in [0x3e0, 0x3f0, 0x1000].iter().enumerate() {
+            class
+                .write_word(&mut dump, 0x2040 + i * 8, FIXTURE_BASE + ptr)
+                .unwrap();
+        }
+
+        let methods = scan_native_methods(&dump, FIXTURE_BASE).unwrap();
+
+        assert_eq!(methods.len(), 1);
+        assert_eq!(methods[0].name, "nativeInit");
+        assert_eq!(methods[0].signature, "(I)V");
+        assert_eq!((methods[0].table, methods[0].function), (0x2040, 0x1000));
+    }
+}
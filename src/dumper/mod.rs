@@ -1,6 +1,12 @@
 pub mod dexdumper;
+pub mod filedumper;
+pub mod hooks;
+pub mod jni;
+pub mod packer;
+pub mod sodiff;
 pub mod sodumper;
 pub mod sofixer;
 
 pub use dexdumper::DexDumper;
+pub use filedumper::FileDumper;
 pub use sodumper::SoDumper;
@@ -0,0 +1,303 @@
+use anyhow::Result;
+use goblin::elf::program_header::{PF_R, PF_W, PF_X};
+use goblin::elf::section_header::{SHT_NOBITS, SHT_NULL};
+
+use crate::utils::{
+    min_load_vaddr, parse_program_headers, read_cstr, read_u32, ElfHeader, ProgramHeader, PAGE_SIZE,
+};
+
+const HIGH_SEGMENT_ENTROPY: f64 = 7.2;
+const HIGH_PAGE_ENTROPY: f64 = 7.5;
+const UPX_SIGNATURES: &[&[u8]] = &[b"UPX!", b"$Info: This file is packed with the UPX"];
+
+#[derive(Debug, Clone)]
+pub struct PageEntropy {
+    pub vaddr: u64,
+    pub entropy: f64,
+    pub disk_entropy: Option<f64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SegmentEntropy {
+    pub vaddr: u64,
+    pub memsz: u64,
+    pub flags: u32,
+    pub entropy: f64,
+    pub disk_entropy: Option<f64>,
+    pub pages: Vec<PageEntropy>,
+}
+
+impl SegmentEntropy {
+    pub fn is_exec(&self) -> bool {
+        self.flags & PF_X != 0
+    }
+
+    pub fn high_pages(&self) -> usize {
+        self.pages
+            .iter()
+            .filter(|p| p.entropy >= HIGH_PAGE_ENTROPY)
+            .count()
+    }
+
+    pub fn permissions(&self) -> String {
+        [(PF_R, 'r'), (PF_W, 'w'), (PF_X, 'x')]
+            .iter()
+            .map(|(flag, c)| if self.flags & flag != 0 { *c } else { '-' })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Clean,
+    Packed,
+    PartiallyEncrypted,
+    Encrypted,
+    Decrypted,
+}
+
+impl std::fmt::Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Verdict::Clean => "clean",
+            Verdict::Packed => "packer signatures found, code entropy normal",
+            Verdict::PartiallyEncrypted => "partially encrypted",
+            Verdict::Encrypted => "still encrypted or compressed",
+            Verdict::Decrypted => "decrypted in memory",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+/// dump出的SO的熵分析和加壳特征检测结果
+#[derive(Debug)]
+pub struct PackerReport {
+    pub segments: Vec<SegmentEntropy>,
+    pub signatures: Vec<String>,
+    pub verdict: Verdict,
+}
+
+/// 每字节的香农熵，范围0~8
+pub fn shannon_entropy(data: &[u8]) -> f64 {
+    if data.is_empty() {
+        return 0.0;
+    }
+    let mut counts = [0usize; 256];
+    for &b in data {
+        counts[b as usize] += 1;
+    }
+
+    // 只有一种字节值时熵为0，避免求和得到-0.0
+    if counts.iter().filter(|&&c| c > 0).count() == 1 {
+        return 0.0;
+    }
+
+    let len = data.len() as f64;
+    counts
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+// 磁盘文件中与dump段p_vaddr相同的PT_LOAD的文件内容
+fn disk_segment<'a>(disk: &'a [u8], phdrs: &[ProgramHeader], vaddr: u64) -> Option<&'a [u8]> {
+    let phdr = phdrs.iter().find(|p| p.is_load() && p.p_vaddr == vaddr)?;
+    let start = phdr.p_offset as usize;
+    disk.get(start..start.checked_add(phdr.p_filesz as usize)?.min(disk.len()))
+}
+
+/// 检查节区头表是否被清空、截断或伪造
+fn section_signatures(data: &[u8]) -> Vec<String> {
+    let Ok(header) = ElfHeader::parse(data) else {
+        return Vec::new();
+    };
+    let class = header.class;
+    let shdr_size = class.shdr_size();
+    let ptr = class.ptr_size();
+
+    if header.e_shoff == 0 || header.e_shnum == 0 {
+        return vec!["no section headers".to_string()];
+    }
+    let mut signatures = Vec::new();
+    if header.e_shentsize as usize != shdr_size {
+        signatures.push(format!("invalid e_shentsize {}", header.e_shentsize));
+        return signatures;
+    }
+    let table_end = header.e_shoff as usize + header.e_shnum as usize * shdr_size;
+    if table_end > data.len() {
+        signatures.push("section headers beyond end of file".to_string());
+        return signatures;
+    }
+    if header.e_shstrndx >= header.e_shnum {
+        signatures.push(format!("invalid e_shstrndx {}", header.e_shstrndx));
+    }
+
+    let section = |index: usize| {
+        let offset = header.e_shoff as usize + index * shdr_size;
+        Some((
+            read_u32(data, offset)?,
+            read_u32(data, offset + 4)?,
+            class.read_word(data, offset + 8 + ptr * 2)?,
+            class.read_word(data, offset + 8 + ptr * 3)?,
+        ))
+    };
+    let shstrtab = section(header.e_shstrndx as usize).map(|(_, _, offset, _)| offset as usize);
+
+    let (mut zero_sized, mut out_of_bounds) = (0, 0);
+    for index in 1..header.e_shnum as usize {
+        let Some((sh_name, sh_type, sh_offset, sh_size)) = section(index) else {
+            continue;
+        };
+        if sh_type == SHT_NULL {
+            continue;
+        }
+        if sh_size == 0 {
+            zero_sized += 1;
+        } else if sh_type != SHT_NOBITS && sh_offset.saturating_add(sh_size) > data.len() as u64 {
+            out_of_bounds += 1;
+        }
+        let name = shstrtab.and_then(|offset| read_cstr(data, offset + sh_name as usize));
+        if let Some(name) = name.filter(|name| name.starts_with("UPX")) {
+            signatures.push(format!("UPX section {}", name));
+        }
+    }
+
+    if zero_sized > 0 {
+        signatures.push(format!("{} zero-sized section headers", zero_sized));
+    }
+    if out_of_bounds > 0 {
+        signatures.push(format!("{} sections beyond end of file", out_of_bounds));
+    }
+    signatures
+}
+
+/// 计算dump各PT_LOAD段和每页的熵并与磁盘文件对比，结合加壳特征给出结论
+pub fn analyze(dump: &[u8], disk: Option<&[u8]>) -> Result<PackerReport> {
+    let header = ElfHeader::parse(dump)?;
+    let phdrs = parse_program_headers(dump, &header)?;
+    let min_vaddr = min_load_vaddr(&phdrs);
+    let disk_phdrs = disk
+        .and_then(|disk| {
+            let header = ElfHeader::parse(disk).ok()?;
+            parse_program_headers(disk, &header).ok()
+        })
+        .unwrap_or_default();
+
+    let mut segments = Vec::new();
+    let mut signatures = Vec::new();
+
+    for phdr in phdrs.iter().filter(|p| p.is_load() && p.p_memsz > 0) {
+        let start = (phdr.p_vaddr - min_vaddr) as usize;
+        let Some(data) = dump.get(start..(start + phdr.p_memsz as usize).min(dump.len())) else {
+            continue;
+        };
+        let on_disk = disk.and_then(|disk| disk_segment(disk, &disk_phdrs, phdr.p_vaddr));
+
+        let pages = data
+            .chunks(PAGE_SIZE as usize)
+            .enumerate()
+            .map(|(i, page)| {
+                let offset = i * PAGE_SIZE as usize;
+                PageEntropy {
+                    vaddr: phdr.p_vaddr + offset as u64,
+                    entropy: shannon_entropy(page),
+                    disk_entropy: on_disk
+                        .and_then(|d| d.get(offset..(offset + page.len()).min(d.len())))
+                        .filter(|d| !d.is_empty())
+                        .map(shannon_entropy),
+                }
+            })
+            .collect();
+
+        if phdr.p_flags & PF_X != 0 {
+            if phdr.p_flags & PF_W != 0 {
+                signatures.push(format!(
+                    "writable and executable segment {:#x}",
+                    phdr.p_vaddr
+                ));
+            }
+            let filesz = disk_phdrs
+                .iter()
+                .find(|p| p.is_load() && p.p_vaddr == phdr.p_vaddr)
+                .map_or(phdr.p_filesz, |p| p.p_filesz);
+            if filesz < phdr.p_memsz {
+                signatures.push(format!(
+                    "executable segment {:#x} is larger in memory than in the file",
+                    phdr.p_vaddr
+                ));
+            }
+        }
+
+        segments.push(SegmentEntropy {
+            vaddr: phdr.p_vaddr,
+            memsz: phdr.p_memsz,
+            flags: phdr.p_flags,
+            entropy: shannon_entropy(data),
+            disk_entropy: on_disk.filter(|d| !d.is_empty()).map(shannon_entropy),
+            pages,
+        });
+    }
+
+    let image = disk.unwrap_or(dump);
+    for signature in UPX_SIGNATURES {
+        if contains(image, signature) {
+            signatures.push(format!(
+                "UPX signature {:?}",
+                String::from_utf8_lossy(signature)
+            ));
+        }
+    }
+    // dump中的节区头表通常没有被映射，只检查磁盘文件
+    if let Some(disk) = disk {
+        signatures.extend(section_signatures(disk));
+    }
+
+    let exec = || segments.iter().filter(|s| s.is_exec());
+    let verdict = if exec().any(|s| s.entropy >= HIGH_SEGMENT_ENTROPY) {
+        Verdict::Encrypted
+    } else if exec().any(|s| s.disk_entropy.is_some_and(|e| e >= HIGH_SEGMENT_ENTROPY)) {
+        Verdict::Decrypted
+    } else if exec().any(|s| s.high_pages() > 0) {
+        Verdict::PartiallyEncrypted
+    } else if !signatures.is_empty() {
+        Verdict::Packed
+    } else {
+        Verdict::Clean
+    };
+
+    Ok(PackerReport {
+        segments,
+        signatures,
+        verdict,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entropy_bounds() {
+        let uniform: Vec<u8> = (0..=255).cycle().take(4096).collect();
+
+        assert_eq!(shannon_entropy(&[]), 0.0);
+        assert!(shannon_entropy(&[0u8; 4096]).is_sign_positive());
+        assert_eq!(shannon_entropy(&[0u8; 4096]), 0.0);
+        assert_eq!(shannon_entropy(&[0x90u8; 16]), 0.0);
+        assert_eq!(shannon_entropy(&[0, 1, 0, 1]), 1.0);
+        assert!((shannon_entropy(&uniform) - 8.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn zero_page_formats_without_sign() {
+        assert_eq!(format!("{:.2}", shannon_entropy(&[0u8; 64])), "0.00");
+    }
+}
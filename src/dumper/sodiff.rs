@@ -0,0 +1,152 @@
+use goblin::elf::header::EM_ARM;
+use goblin::elf::section_header::SHN_UNDEF;
+use goblin::elf::Elf;
+use std::ops::Range;
+
+/// 已加载SO的文件映射段与磁盘文件对比时，内存中不一致的一段
+#[derive(Debug, Clone)]
+pub struct ModifiedRange {
+    pub address: u64,
+    pub vaddr: u64,
+    pub file_offset: u64,
+    pub size: u64,
+    pub permissions: String,
+    pub symbol: Option<String>,
+}
+
+/// 逐字节对比，返回不一致字节组成的连续区间，ignored返回true的偏移不参与对比
+pub fn diff_bytes(
+    disk: &[u8],
+    memory: &[u8],
+    ignored: &dyn Fn(usize) -> bool,
+) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+
+    for (i, (a, b)) in disk.iter().zip(memory).enumerate() {
+        if a == b || ignored(i) {
+            continue;
+        }
+        match ranges.last_mut() {
+            Some(last) if last.end == i => last.end = i + 1,
+            _ => ranges.push(i..i + 1),
+        }
+    }
+
+    ranges
+}
+
+/// 合并间隔不超过gap字节的区间
+pub fn merge_ranges(ranges: &[Range<usize>], gap: usize) -> Vec<Range<usize>> {
+    let mut merged: Vec<Range<usize>> = Vec::new();
+
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end + gap => last.end = last.end.max(range.end),
+            _ => merged.push(range.clone()),
+        }
+    }
+
+    merged
+}
+
+/// 磁盘文件中.symtab和.dynsym的已定义符号，按地址排序
+pub struct SymbolIndex {
+    symbols: Vec<(u64, String)>,
+}
+
+impl SymbolIndex {
+    pub fn from_file(data: &[u8]) -> Self {
+        let mut symbols = Vec::new();
+
+        if let Ok(elf) = Elf::parse(data) {
+            // ARM的Thumb函数地址最低位为1
+            let mask = if elf.header.e_machine == EM_ARM {
+                !1
+            } else {
+                !0
+            };
+            let syms = elf
+                .syms
+                .iter()
+                .map(|sym| (sym, elf.strtab.get_at(sym.st_name)));
+            let dynsyms = elf
+                .dynsyms
+                .iter()
+                .map(|sym| (sym, elf.dynstrtab.get_at(sym.st_name)));
+
+            for (sym, name) in syms.chain(dynsyms) {
+                let Some(name) = name.filter(|name| !name.is_empty()) else {
+                    continue;
+                };
+                if sym.st_shndx == SHN_UNDEF as usize || sym.st_value == 0 {
+                    continue;
+                }
+                symbols.push((sym.st_value & mask, name.to_string()));
+            }
+        }
+
+        symbols.sort();
+        symbols.dedup_by(|a, b| a.0 == b.0);
+        Self { symbols }
+    }
+
+    /// 返回地址前最近的符号，格式为`name+0x10`
+    pub fn lookup(&self, vaddr: u64) -> Option<String> {
+        let index = self.symbols.partition_point(|(value, _)| *value <= vaddr);
+        let (value, name) = self.symbols.get(index.checked_sub(1)?)?;
+
+        Some(match vaddr - value {
+            0 => name.clone(),
+            offset => format!("{}+{:#x}", name, offset),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dumper::sofixer::SoFixer;
+    use crate::utils::write_u16;
+
+    const FIXTURE: &[u8] = include_bytes!("../../tests/fixtures/libfixture_x86_64_dump.bin");
+    const FIXTURE_BASE: u64 = 0x7f64_1345_c000;
+
+    #[test]
+    fn diff_bytes_skips_ignored_offsets() {
+        let disk = [0u8; 8];
+        let memory = [0, 1, 1, 0, 0, 1, 1, 0];
+
+        assert_eq!(diff_bytes(&disk, &memory, &|_| false), [1..3, 5..7]);
+        // 重定位目标处的差异不计入
+        assert_eq!(diff_bytes(&disk, &memory, &|i| i == 5), [1..3, 6..7]);
+        assert_eq!(diff_bytes(&disk, &memory, &|i| i == 1), [2..3, 5..7]);
+    }
+
+    #[test]
+    fn merge_ranges_within_gap() {
+        let ranges = [0..2, 4..5, 8..9, 20..22];
+
+        assert_eq!(merge_ranges(&ranges, 2), [0..5, 8..9, 20..22]);
+        assert_eq!(merge_ranges(&ranges, 3), [0..9, 20..22]);
+        assert_eq!(merge_ranges(&ranges, 0), ranges);
+    }
+
+    #[test]
+    fn lookup_nearest_symbol() {
+        // fixture_add位于0x1004，fixture_set的值为0x1011
+        let mut image = SoFixer::new().rebuild(FIXTURE, FIXTURE_BASE).unwrap();
+        let index = SymbolIndex::from_file(&image);
+
+        assert_eq!(index.lookup(0x1004).as_deref(), Some("fixture_add"));
+        assert_eq!(index.lookup(0x100c).as_deref(), Some("fixture_add+0x8"));
+        assert_eq!(index.lookup(0x1010).as_deref(), Some("fixture_add+0xc"));
+        assert_eq!(index.lookup(0x10), None);
+
+        // ARM上最低位为Thumb标记，fixture_set实际位于0x1010
+        write_u16(&mut image, 0x12, EM_ARM).unwrap();
+        let index = SymbolIndex::from_file(&image);
+
+        assert_eq!(index.lookup(0x1010).as_deref(), Some("fixture_set"));
+        assert_eq!(index.lookup(0x1011).as_deref(), Some("fixture_set+0x1"));
+    }
+}
@@ -1,124 +1,468 @@
 use anyhow::{anyhow, Result};
 use byteorder::{LittleEndian, ReadBytesExt};
+use goblin::elf::dynamic::{DT_INIT, DT_INIT_ARRAY, DT_INIT_ARRAYSZ, DT_SONAME};
+use goblin::elf::header::EM_ARM;
+use goblin::elf::program_header::{PF_X, PT_DYNAMIC, PT_LOAD, PT_NOTE};
+use goblin::elf::reloc::r_to_str;
 use goblin::elf::Elf;
+use nix::libc;
 use nix::sys::signal::{kill, Signal};
-use nix::unistd::Pid;
-use std::fs::File;
-use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use nix::unistd::{getuid, Pid};
+use regex::Regex;
+use std::cell::OnceCell;
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io::Write;
 use std::path::{Path, PathBuf};
-
-use super::sofixer::SoFixer;
-use crate::utils::{MemoryMapping, SoInfo};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::hooks::decode_trampoline;
+use super::jni::scan_native_methods;
+use super::packer::analyze;
+use super::sodiff::{diff_bytes, merge_ranges, ModifiedRange, SymbolIndex};
+use super::sofixer::{DynSymbol, SoFixer};
+use crate::utils::{
+    default_reader, detect_process_class, find_layout, find_r_debug, get_android_sdk, is_got_type,
+    is_plausible_image, json_string, list_elf_images, list_link_map, load_span, min_load_vaddr,
+    parse_build_id, parse_dynamic, parse_proc_maps, parse_program_headers, probe_elf_image,
+    read_cstr, read_elf_header_file, read_exe_header, read_maps, read_u32, snapshot_pid,
+    so_files_in, write_missing_report, BreakTrigger, ElfClass, ElfHeader, LinkerNamespace,
+    MapSyscall, MemoryMapping, MemoryReader, MissingRanges, ProgramHeader, RelocFormat,
+    SnapshotReader, SoFileInfo, SoInfo, SoInfoLayout, Syscall, TraceEvent, Tracer,
+    DEFAULT_BUILD_PROP, ELF_MAGIC, PAGE_SIZE, SNAPSHOT_EXE, SNAPSHOT_MAPS, SOINFO_LAYOUTS,
+};
 
 // Author: mrack <https://github.com/mrack>
+// JSON输出时进度日志改写到stderr，stdout只保留JSON
+macro_rules! progress {
+    ($dumper:expr, $($arg:tt)*) => {
+        if $dumper.json {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+// 启发式找到的solist至少要串起的soinfo数量
+const MIN_SOLIST_CHAIN: usize = 3;
+
+struct SoList {
+    head: u64,
+    layout: &'static SoInfoLayout,
+    namespace_members: Vec<u64>,
+}
+
 pub struct SoDumper {
     target_pid: u32,
     target_name: String,
+    target_addr: Option<u64>,
     output_dir: PathBuf,
+    class: ElfClass,
+    machine: Option<u16>,
+    build_prop: PathBuf,
+    probe_layout: bool,
+    segment_mode: bool,
+    link_map: bool,
+    namespaces: bool,
+    jni_scan: bool,
+    json: bool,
+    reader: Box<dyn MemoryReader>,
+    // 离线快照目录，设置时不访问目标进程
+    snapshot: Option<PathBuf>,
     sofixer: SoFixer,
     auto_fix: bool,
 }
 
 impl SoDumper {
     pub fn new(target_pid: u32, target_name: String, output_dir: PathBuf) -> Result<Self> {
-        let sofixer = SoFixer::new()?;
+        let sofixer = SoFixer::new();
+        let class = detect_process_class(target_pid)?;
+        let machine = read_exe_header(target_pid).ok().map(|h| h.e_machine);
+        let reader = default_reader(target_pid);
         Ok(Self {
             target_pid,
             target_name,
+            target_addr: None,
             output_dir,
+            class,
+            machine,
+            build_prop: PathBuf::from(DEFAULT_BUILD_PROP),
+            probe_layout: false,
+            segment_mode: false,
+            link_map: false,
+            namespaces: false,
+            jni_scan: false,
+            json: false,
+            reader,
+            snapshot: None,
             sofixer,
             auto_fix: true,
         })
     }
 
-    pub fn extract_sofixer(&self) -> Result<()> {
-        self.sofixer.extract()
+    /// 从write_snapshot写出的快照目录离线dump，不需要目标进程
+    pub fn from_snapshot(
+        snapshot: &Path,
+        target_name: String,
+        output_dir: PathBuf,
+    ) -> Result<Self> {
+        let header = read_elf_header_file(&snapshot.join(SNAPSHOT_EXE))?;
+        Ok(Self {
+            target_pid: snapshot_pid(snapshot),
+            target_name,
+            target_addr: None,
+            output_dir,
+            class: header.class,
+            machine: Some(header.e_machine),
+            build_prop: PathBuf::from(DEFAULT_BUILD_PROP),
+            probe_layout: false,
+            segment_mode: false,
+            link_map: false,
+            namespaces: false,
+            jni_scan: false,
+            json: false,
+            reader: Box::new(SnapshotReader::new(snapshot)?),
+            snapshot: Some(snapshot.to_path_buf()),
+            sofixer: SoFixer::new(),
+            auto_fix: true,
+        })
+    }
+
+    /// 按地址指定目标，用于没有文件名的内存SO
+    pub fn set_target_addr(&mut self, target_addr: Option<u64>) {
+        self.target_addr = target_addr;
+    }
+
+    pub fn set_build_prop(&mut self, build_prop: PathBuf) {
+        self.build_prop = build_prop;
+    }
+
+    pub fn set_probe_layout(&mut self, probe_layout: bool) {
+        self.probe_layout = probe_layout;
+    }
+
+    pub fn set_segment_mode(&mut self, segment_mode: bool) {
+        self.segment_mode = segment_mode;
+    }
+
+    /// 使用r_debug/link_map代替bionic的solist枚举库，用于glibc等非Android进程
+    pub fn set_link_map(&mut self, link_map: bool) {
+        self.link_map = link_map;
+    }
+
+    /// 遍历所有linker namespace，解析SO大小时使用各namespace成员的并集
+    pub fn set_namespaces(&mut self, namespaces: bool) {
+        self.namespaces = namespaces;
+    }
+
+    /// dump后扫描RegisterNatives注册的JNINativeMethod表
+    pub fn set_jni_scan(&mut self, jni_scan: bool) {
+        self.jni_scan = jni_scan;
+    }
+
+    /// 以JSON输出结果，进度日志改写到stderr
+    pub fn set_json(&mut self, json: bool) {
+        self.json = json;
+    }
+
+    pub fn set_rebase(&mut self, rebase: Option<u64>) {
+        self.sofixer.set_rebase(rebase);
+    }
+
+    pub fn set_reader(&mut self, reader: Box<dyn MemoryReader>) {
+        self.reader = reader;
+    }
+
+    fn linker_name(&self) -> &'static str {
+        match self.class {
+            ElfClass::Elf32 => "linker",
+            ElfClass::Elf64 => "linker64",
+        }
+    }
+
+    fn read_linker_image(&self) -> Result<Vec<u8>> {
+        let mapping = self.get_linker_mapping()?;
+        let (path, buffer) = self.read_mapped_file(&mapping)?;
+        progress!(self, "[+] {} image: {}", self.linker_name(), path);
+        Ok(buffer)
+    }
+
+    /// 读取映射对应的文件，依次尝试map_files、目标的mount namespace和本地路径
+    fn read_mapped_file(&self, mapping: &MemoryMapping) -> Result<(String, Vec<u8>)> {
+        let candidates = [
+            format!(
+                "/proc/{}/map_files/{:x}-{:x}",
+                self.target_pid, mapping.start, mapping.end
+            ),
+            format!("/proc/{}/root{}", self.target_pid, mapping.pathname),
+            mapping.pathname.clone(),
+        ];
+
+        for path in candidates {
+            match fs::read(&path) {
+                Ok(buffer) => return Ok((path, buffer)),
+                Err(e) => progress!(self, "[*] Failed to read {}: {}", path, e),
+            }
+        }
+
+        Err(anyhow!("Could not read {}", mapping.pathname))
     }
 
     fn get_solist_offset(&self) -> Result<u64> {
-        let linker_path = "/system/bin/linker64";
-        let mut file = File::open(linker_path)?;
-        let mut buffer = Vec::new();
-        file.read_to_end(&mut buffer)?;
+        self.find_linker_symbol("__dl__ZL6solist")
+    }
 
+    /// 在linker符号表中查找符号，返回相对linker基址的偏移
+    fn find_linker_symbol(&self, symbol: &str) -> Result<u64> {
+        let buffer = self.read_linker_image()?;
         let elf = Elf::parse(&buffer)?;
+        let min_vaddr = elf
+            .program_headers
+            .iter()
+            .filter(|p| p.p_type == PT_LOAD)
+            .map(|p| p.p_vaddr & !(PAGE_SIZE - 1))
+            .min()
+            .unwrap_or(0);
 
         for sym in elf.syms.iter() {
             if let Some(name) = elf.strtab.get_at(sym.st_name) {
-                if name.contains("__dl__ZL6solist") {
-                    return Ok(sym.st_value);
+                if name.contains(symbol) {
+                    return Ok(sym.st_value - min_vaddr);
+                }
+            }
+        }
+
+        Err(anyhow!(
+            "Could not find {} symbol in {}",
+            symbol,
+            self.linker_name()
+        ))
+    }
+
+    /// 符号被strip时，从缓存或linker数据段中启发式查找solist
+    fn find_solist_offset(&self) -> Result<u64> {
+        let err = match self.get_solist_offset() {
+            Ok(offset) => return Ok(offset),
+            Err(e) => e,
+        };
+        progress!(self, "[*] {}, searching linker data for solist", err);
+
+        let linker_base = self.get_linker_base()?;
+        let build_id = self.linker_build_id(linker_base);
+        if let Some(offset) = build_id.as_deref().and_then(Self::cached_solist_offset) {
+            if self.is_solist_at(linker_base + offset) {
+                progress!(self, "[+] solist offset from cache: {:#x}", offset);
+                return Ok(offset);
+            }
+            progress!(
+                self,
+                "[*] Cached solist offset {:#x} is stale, rescanning",
+                offset
+            );
+        }
+
+        let offset = self.scan_solist_offset(linker_base)?;
+        if let Some(build_id) = build_id {
+            if let Err(e) = Self::cache_solist_offset(&build_id, offset) {
+                eprintln!("[!] Failed to cache solist offset: {}", e);
+            }
+        }
+        Ok(offset)
+    }
+
+    fn linker_build_id(&self, linker_base: u64) -> Option<String> {
+        let (_, phdrs) = self.read_elf_phdrs(linker_base).ok()?;
+        let min_vaddr = min_load_vaddr(&phdrs);
+        phdrs.iter().filter(|p| p.p_type == PT_NOTE).find_map(|p| {
+            let address = linker_base + p.p_vaddr - min_vaddr;
+            let notes = self.read_process_memory(address, p.p_memsz as usize).ok()?;
+            parse_build_id(&notes)
+        })
+    }
+
+    // 缓存文件每行为`<build-id> <offset>`，按用户区分，避免读到其他用户写入的文件
+    fn solist_cache_path() -> PathBuf {
+        std::env::temp_dir().join(format!("tinydump_solist_{}.cache", getuid()))
+    }
+
+    fn cached_solist_offset(build_id: &str) -> Option<u64> {
+        let content = fs::read_to_string(Self::solist_cache_path()).ok()?;
+        content.lines().find_map(|line| {
+            let (id, offset) = line.split_once(' ')?;
+            (id == build_id)
+                .then(|| u64::from_str_radix(offset.trim_start_matches("0x"), 16).ok())
+                .flatten()
+        })
+    }
+
+    fn cache_solist_offset(build_id: &str, offset: u64) -> Result<()> {
+        let path = Self::solist_cache_path();
+        let mut content: String = fs::read_to_string(&path)
+            .unwrap_or_default()
+            .lines()
+            .filter(|line| line.split_once(' ').is_none_or(|(id, _)| id != build_id))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        content.push_str(&format!("{} {:#x}\n", build_id, offset));
+        fs::write(&path, content)?;
+        Ok(())
+    }
+
+    fn solist_layouts(&self) -> Vec<&'static SoInfoLayout> {
+        SOINFO_LAYOUTS
+            .iter()
+            .filter(|l| l.matches(self.class, self.machine))
+            .collect()
+    }
+
+    // 缓存的偏移使用前重新校验，指向的链表要能串起足够多的soinfo
+    fn is_solist_at(&self, address: u64) -> bool {
+        let (Ok(mappings), Ok(data)) = (
+            self.parse_proc_maps(),
+            self.read_process_memory(address, self.class.ptr_size()),
+        ) else {
+            return false;
+        };
+        let Ok(head) = self.read_pointer(&data, 0) else {
+            return false;
+        };
+        self.solist_layouts().iter().any(|layout| {
+            self.score_layout(layout, head, &mappings, MIN_SOLIST_CHAIN) >= MIN_SOLIST_CHAIN
+        })
+    }
+
+    /// 扫描linker的可写段及其后的.bss匿名映射，找出soinfo链最长的指针
+    fn scan_solist_offset(&self, linker_base: u64) -> Result<u64> {
+        let mappings = self.parse_proc_maps()?;
+        let suffix = format!("/{}", self.linker_name());
+
+        let mut regions = Vec::new();
+        let mut after_linker = false;
+        for mapping in &mappings {
+            let writable = mapping.permissions.starts_with("rw");
+            if mapping.pathname.ends_with(&suffix) {
+                if writable {
+                    regions.push((mapping.start, mapping.end));
+                }
+                after_linker = true;
+            } else if after_linker && writable && mapping.pathname.is_empty() {
+                regions.push((mapping.start, mapping.end));
+                after_linker = false;
+            } else {
+                after_linker = false;
+            }
+        }
+
+        // soinfo由linker分配在[anon:linker_alloc]中，内核不显示匿名映射名时退回到所有可写映射
+        let mut allocations: Vec<(u64, u64)> = mappings
+            .iter()
+            .filter(|m| m.pathname.starts_with("[anon:linker_alloc"))
+            .map(|m| (m.start, m.end))
+            .collect();
+        if allocations.is_empty() {
+            allocations = mappings
+                .iter()
+                .filter(|m| m.permissions.starts_with("rw"))
+                .map(|m| (m.start, m.end))
+                .collect();
+        }
+        // 链上已串起maps中大部分SO时即为solist，不再扫描剩余数据
+        let enough = (so_files_in(&mappings).len() * 3 / 4).max(MIN_SOLIST_CHAIN);
+
+        let layouts = self.solist_layouts();
+        let ptr_size = self.class.ptr_size();
+        let mut best: Option<(u64, usize, &str)> = None;
+
+        'scan: for (start, end) in regions {
+            let (data, _) = self.reader.read_tolerant(start, (end - start) as usize);
+            for offset in (0..data.len()).step_by(ptr_size) {
+                let value = self.read_pointer(&data, offset)?;
+                if value == 0
+                    || !allocations
+                        .iter()
+                        .any(|&(start, end)| value >= start && value < end)
+                {
+                    continue;
+                }
+
+                for layout in &layouts {
+                    let score = self.score_layout(layout, value, &mappings, enough);
+                    if score >= MIN_SOLIST_CHAIN && best.is_none_or(|(_, best, _)| score > best) {
+                        best = Some((start + offset as u64, score, layout.name));
+                    }
+                    if score >= enough {
+                        break 'scan;
+                    }
                 }
             }
         }
 
-        Err(anyhow!("Could not find solist symbol in linker64"))
+        let (address, score, layout) =
+            best.ok_or_else(|| anyhow!("Could not find solist in linker data"))?;
+        progress!(
+            self,
+            "[+] Found solist candidate at {:#x} ({} soinfo, layout {})",
+            address,
+            score,
+            layout
+        );
+        Ok(address - linker_base)
     }
 
     fn stop_process(&self) -> Result<()> {
+        if self.snapshot.is_some() {
+            return Ok(());
+        }
         kill(Pid::from_raw(self.target_pid as i32), Signal::SIGSTOP)?;
-        println!("[+] Process {} stopped", self.target_pid);
+        progress!(self, "[+] Process {} stopped", self.target_pid);
         Ok(())
     }
 
     fn continue_process(&self) -> Result<()> {
+        if self.snapshot.is_some() {
+            return Ok(());
+        }
         kill(Pid::from_raw(self.target_pid as i32), Signal::SIGCONT)?;
-        println!("[+] Process {} continued", self.target_pid);
+        progress!(self, "[+] Process {} continued", self.target_pid);
         Ok(())
     }
 
     fn parse_proc_maps(&self) -> Result<Vec<MemoryMapping>> {
-        let maps_path = format!("/proc/{}/maps", self.target_pid);
-        let file = File::open(&maps_path)?;
-        let reader = BufReader::new(file);
-        let mut mappings = Vec::new();
-
-        for line in reader.lines() {
-            let line = line?;
-            let parts: Vec<&str> = line.split_whitespace().collect();
-
-            if parts.len() >= 6 {
-                let addr_range: Vec<&str> = parts[0].split('-').collect();
-                let start = u64::from_str_radix(addr_range[0], 16)?;
-                let end = u64::from_str_radix(addr_range[1], 16)?;
-                let permissions = parts[1].to_string();
-                let offset = u64::from_str_radix(parts[2], 16)?;
-                let device = parts[3].to_string();
-                let inode = parts[4].parse::<u64>()?;
-                let pathname = if parts.len() > 5 {
-                    parts[5].to_string()
-                } else {
-                    String::new()
-                };
-
-                mappings.push(MemoryMapping {
-                    start,
-                    end,
-                    permissions,
-                    offset,
-                    device,
-                    inode,
-                    pathname,
-                });
-            }
+        match &self.snapshot {
+            Some(snapshot) => read_maps(&snapshot.join(SNAPSHOT_MAPS)),
+            None => parse_proc_maps(self.target_pid),
         }
+    }
 
-        Ok(mappings)
+    // 按文件名匹配，兼容/system/bin和APEX中的linker
+    fn get_linker_mapping(&self) -> Result<MemoryMapping> {
+        self.parse_proc_maps()?
+            .into_iter()
+            .find(|m| Path::new(&m.pathname).file_name() == Some(OsStr::new(self.linker_name())))
+            .ok_or_else(|| anyhow!("Could not find {} in process maps", self.linker_name()))
     }
 
     fn get_linker_base(&self) -> Result<u64> {
-        let mappings = self.parse_proc_maps()?;
-
-        for mapping in mappings {
-            if mapping.pathname.contains("linker64") {
-                return Ok(mapping.start);
-            }
-        }
-
-        Err(anyhow!("Could not find linker64 in process maps"))
+        Ok(self.get_linker_mapping()?.start)
     }
 
     fn get_target_mapping(&self) -> Result<(u64, u64)> {
         let mappings = self.parse_proc_maps()?;
+
+        if let Some(addr) = self.target_addr {
+            let mapping = mappings
+                .iter()
+                .find(|m| addr >= m.start && addr < m.end)
+                .ok_or_else(|| anyhow!("Address {:#x} is not mapped", addr))?;
+            let end = match self.phdr_size(addr) {
+                Ok(size) => addr + size,
+                Err(_) => mapping.end,
+            };
+            return Ok((addr, end));
+        }
         let mut target_start = None;
         let mut target_end = None;
 
@@ -132,13 +476,7 @@ impl SoDumper {
         }
 
         match (target_start, target_end) {
-            (Some(start), Some(end)) => {
-                if start > 0x7fffffff {
-                    Ok((start, end))
-                } else {
-                    Err(anyhow!("Target SO is 32-bit (base: {:#x}), only 64-bit SO files are supported. 32-bit SO files have base addresses below 0x80000000.", start))
-                }
-            }
+            (Some(start), Some(end)) => Ok((start, end)),
             _ => Err(anyhow!(
                 "Could not find target {} in process maps",
                 self.target_name
@@ -147,52 +485,155 @@ impl SoDumper {
     }
 
     fn read_process_memory(&self, address: u64, size: usize) -> Result<Vec<u8>> {
-        let mem_path = format!("/proc/{}/mem", self.target_pid);
-        let mut file = File::open(&mem_path)?;
-        file.seek(SeekFrom::Start(address))?;
-
-        let mut buffer = vec![0u8; size];
-        file.read_exact(&mut buffer)?;
+        self.reader.read_bytes(address, size)
+    }
 
-        Ok(buffer)
+    fn read_pointer(&self, data: &[u8], offset: usize) -> Result<u64> {
+        let mut cursor = std::io::Cursor::new(&data[offset..offset + self.class.ptr_size()]);
+        let value = match self.class {
+            ElfClass::Elf32 => cursor.read_u32::<LittleEndian>()? as u64,
+            ElfClass::Elf64 => cursor.read_u64::<LittleEndian>()?,
+        };
+        Ok(value)
     }
 
     fn get_solist_head(&self, solist_addr: u64) -> Result<u64> {
-        let data = self.read_process_memory(solist_addr, 8)?;
-        let mut cursor = std::io::Cursor::new(data);
-        let solist_head = cursor.read_u64::<LittleEndian>()?;
+        let data = self.read_process_memory(solist_addr, self.class.ptr_size())?;
+        let solist_head = self.read_pointer(&data, 0)?;
 
-        println!("[*] solist head: {:#x}", solist_head);
+        progress!(self, "[*] solist head: {:#x}", solist_head);
         Ok(solist_head)
     }
 
-    fn parse_soinfo(&self, soinfo_addr: u64) -> Result<SoInfo> {
-        let data = self.read_process_memory(soinfo_addr, 256)?;
+    fn parse_soinfo(&self, soinfo_addr: u64, layout: &SoInfoLayout) -> Result<SoInfo> {
+        let data = self.read_process_memory(soinfo_addr, 0x200)?;
+
+        Ok(SoInfo {
+            phdr: self.read_pointer(&data, layout.phdr)?,
+            phnum: self.read_pointer(&data, layout.phnum)?,
+            base: self.read_pointer(&data, layout.base)?,
+            size: self.read_pointer(&data, layout.size)?,
+            dynamic: self.read_pointer(&data, layout.dynamic)?,
+            next: self.read_pointer(&data, layout.next)?,
+            flags: read_u32(&data, layout.flags).unwrap_or(0),
+            strtab: self.read_pointer(&data, layout.strtab)?,
+            symtab: self.read_pointer(&data, layout.symtab)?,
+            init_array: self.read_pointer(&data, layout.init_array)?,
+            init_array_count: self.read_pointer(&data, layout.init_array_count)?,
+            fini_array: self.read_pointer(&data, layout.fini_array)?,
+            fini_array_count: self.read_pointer(&data, layout.fini_array_count)?,
+            constructors_called: data[layout.constructors_called] != 0,
+            load_bias: self.read_pointer(&data, layout.load_bias)?,
+        })
+    }
+
+    fn select_layout(&self, solist_head: u64) -> Result<&'static SoInfoLayout> {
+        let api = get_android_sdk(&self.build_prop);
+
+        if self.probe_layout {
+            const MAX_PROBE: usize = 16;
+            let mappings = self.parse_proc_maps()?;
+            let best = SOINFO_LAYOUTS
+                .iter()
+                .filter(|l| l.class == self.class)
+                .map(|l| (l, self.score_layout(l, solist_head, &mappings, MAX_PROBE)))
+                .inspect(|(l, score)| {
+                    progress!(self, "[*] layout {} matched {} soinfo", l.name, score)
+                })
+                // 多个API的布局前缀相同，得分相同时按API版本选择
+                .max_by_key(|(l, score)| {
+                    (
+                        *score,
+                        l.matches(self.class, self.machine),
+                        api.as_ref().is_ok_and(|api| l.supports_api(*api)),
+                    )
+                });
+
+            if let Some((layout, score)) = best.filter(|(_, score)| *score > 0) {
+                progress!(
+                    self,
+                    "[+] soinfo layout: {} (probed, {} matches)",
+                    layout.name,
+                    score
+                );
+                return Ok(layout);
+            }
+            progress!(self, "[*] Layout probing failed, falling back to API level");
+        }
+
+        let api = match api {
+            Ok(api) => Some(api),
+            Err(e) => {
+                progress!(self, "[*] {}, using latest layout", e);
+                None
+            }
+        };
+        let layout = find_layout(self.class, self.machine, api)
+            .ok_or_else(|| anyhow!("No soinfo layout for {} API {:?}", self.class, api))?;
+
+        match api {
+            Some(api) => progress!(self, "[+] soinfo layout: {} (API {})", layout.name, api),
+            None => progress!(self, "[+] soinfo layout: {} (API unknown)", layout.name),
+        }
+        Ok(layout)
+    }
 
-        const PTR_SIZE: usize = 8;
-        const OFF_BASE: usize = 0x10;
-        const OFF_SIZE: usize = 0x18;
-        const OFF_NEXT: usize = 0x28;
+    // 沿soinfo链校验，base必须是某个映射的起始地址，dynamic必须落在[base, base+size)内
+    fn score_layout(
+        &self,
+        layout: &SoInfoLayout,
+        solist_head: u64,
+        mappings: &[MemoryMapping],
+        max_probe: usize,
+    ) -> usize {
+        let mut current = solist_head;
+        let mut score = 0;
+
+        for _ in 0..max_probe {
+            if current == 0 {
+                break;
+            }
+            let soinfo = match self.parse_soinfo(current, layout) {
+                Ok(soinfo) => soinfo,
+                Err(_) => break,
+            };
+
+            // 早期版本链表头是base为0的libdl.so占位soinfo
+            if !Self::is_placeholder_soinfo(&soinfo) {
+                if !Self::is_valid_soinfo(&soinfo, mappings) {
+                    break;
+                }
+                score += 1;
+            }
 
-        let mut cursor = std::io::Cursor::new(&data[OFF_BASE..OFF_BASE + PTR_SIZE]);
-        let base = cursor.read_u64::<LittleEndian>()?;
+            current = soinfo.next;
+        }
 
-        let mut cursor = std::io::Cursor::new(&data[OFF_SIZE..OFF_SIZE + PTR_SIZE]);
-        let size = cursor.read_u64::<LittleEndian>()?;
+        score
+    }
 
-        let mut cursor = std::io::Cursor::new(&data[OFF_NEXT..OFF_NEXT + PTR_SIZE]);
-        let next = cursor.read_u64::<LittleEndian>()?;
+    fn is_placeholder_soinfo(soinfo: &SoInfo) -> bool {
+        soinfo.base == 0 && soinfo.size == 0
+    }
 
-        Ok(SoInfo { base, size, next })
+    fn is_valid_soinfo(soinfo: &SoInfo, mappings: &[MemoryMapping]) -> bool {
+        mappings.iter().any(|m| m.start == soinfo.base)
+            && soinfo.size > 0
+            && soinfo.size < 1 << 32
+            && soinfo.load_bias <= soinfo.base
+            && (soinfo.dynamic == 0
+                || (soinfo.dynamic >= soinfo.base
+                    && soinfo.dynamic < soinfo.base.saturating_add(soinfo.size)))
     }
 
-    fn find_target_soinfo(&self, solist_head: u64, target_base: u64) -> Result<u64> {
-        let mut current_soinfo = solist_head;
+    fn find_target_soinfo(&self, solist: &SoList, target_base: u64) -> Result<u64> {
+        let layout = solist.layout;
+        let mut current_soinfo = solist.head;
         let mut iteration_count = 0;
         const MAX_ITERATIONS: usize = 1000;
 
         while current_soinfo != 0 && iteration_count < MAX_ITERATIONS {
-            let soinfo = self.parse_soinfo(current_soinfo)?;
+            let soinfo = self.parse_soinfo(current_soinfo, layout)?;
 
             println!(
                 "[*] soinfo base: {:#x}, size: {:#x}, next: {:#x}",
@@ -207,20 +648,29 @@ impl SoDumper {
             iteration_count += 1;
         }
 
+        // solist中没有时再查找各namespace的成员
+        for &member in &solist.namespace_members {
+            let soinfo = self.parse_soinfo(member, layout)?;
+            if soinfo.base == target_base {
+                println!("[+] Found target in namespace soinfo {:#x}", member);
+                return Ok(soinfo.size);
+            }
+        }
+
         Err(anyhow!("Could not find target SO in soinfo chain"))
     }
 
     fn search_soinfo_chain(&self, solist_head: u64, target_base: u64) -> Result<u64> {
-        let chain_data = self.read_process_memory(solist_head, 256 * 1024)?;
+        let (chain_data, _) = self.reader.read_tolerant(solist_head, 256 * 1024);
 
-        let target_pattern = target_base.to_le_bytes();
+        let ptr_size = self.class.ptr_size();
+        let target_pattern = &target_base.to_le_bytes()[..ptr_size];
 
-        for window in chain_data.windows(target_pattern.len()) {
+        for window in chain_data.windows(ptr_size) {
             if window == target_pattern {
-                let offset = window.as_ptr() as usize - chain_data.as_ptr() as usize + 8;
-                if offset + 8 <= chain_data.len() {
-                    let mut cursor = std::io::Cursor::new(&chain_data[offset..offset + 8]);
-                    if let Ok(size) = cursor.read_u64::<LittleEndian>() {
+                let offset = window.as_ptr() as usize - chain_data.as_ptr() as usize + ptr_size;
+                if offset + ptr_size <= chain_data.len() {
+                    if let Ok(size) = self.read_pointer(&chain_data, offset) {
                         println!("[+] Found soinfo size: {}", size);
                         return Ok(size);
                     }
@@ -231,12 +681,102 @@ impl SoDumper {
         Err(anyhow!("Could not find target base in soinfo chain data"))
     }
 
-    fn dump_so(&self, target_base: u64, so_size: u64) -> Result<PathBuf> {
+    // 直接从目标内存解析ELF头和程序头，加壳后可能被篡改，程序头表必须位于第一页内
+    fn read_elf_phdrs(&self, base: u64) -> Result<(ElfHeader, Vec<ProgramHeader>)> {
+        let ehdr = self.read_process_memory(base, self.class.ehdr_size())?;
+        let header = ElfHeader::parse(&ehdr)?;
+
+        let table_end = header
+            .e_phoff
+            .checked_add(header.e_phnum as u64 * header.class.phdr_size() as u64)
+            .filter(|end| *end <= PAGE_SIZE)
+            .ok_or_else(|| anyhow!("Program header table at {:#x} is out of range", base))?;
+        let data = self.read_process_memory(base, table_end as usize)?;
+        let phdrs = parse_program_headers(&data, &header)?;
+        if !is_plausible_image(&header, &phdrs) {
+            return Err(anyhow!("Implausible program headers at {:#x}", base));
+        }
+
+        Ok((header, phdrs))
+    }
+
+    fn phdr_size(&self, target_base: u64) -> Result<u64> {
+        let (_, phdrs) = self.read_elf_phdrs(target_base)?;
+        let size = load_span(&phdrs);
+        if size == 0 {
+            return Err(anyhow!("No PT_LOAD segment at {:#x}", target_base));
+        }
+        println!("[+] Found program header size: {}", size);
+        Ok(size)
+    }
+
+    fn dump_so(&self, so_name: &str, target_base: u64, so_size: u64) -> Result<PathBuf> {
         println!("[+] Dumping SO from {:#x}, size: {}", target_base, so_size);
 
-        let data = self.read_process_memory(target_base, so_size as usize)?;
+        let (data, missing) = self.reader.read_tolerant(target_base, so_size as usize);
+        if missing.len() == 1 && missing[0] == (target_base, target_base + so_size) {
+            return Err(anyhow!("Failed to read any memory at {:#x}", target_base));
+        }
 
-        let base_name = Path::new(&self.target_name)
+        self.save_dump(so_name, target_base, &data, &missing)
+    }
+
+    /// 按内存中的程序头逐个读取PT_LOAD段，并按p_vaddr排布，段之间的空洞填0
+    fn dump_so_segments(&self, so_name: &str, target_base: u64) -> Result<PathBuf> {
+        const MAX_SPAN: u64 = 0x20000000;
+
+        let (_, phdrs) = self.read_elf_phdrs(target_base)?;
+        let min_vaddr = min_load_vaddr(&phdrs);
+        let span = load_span(&phdrs);
+        if span > MAX_SPAN {
+            return Err(anyhow!(
+                "PT_LOAD span {:#x} at {:#x} is too large",
+                span,
+                target_base
+            ));
+        }
+        println!(
+            "[+] Dumping SO segments from {:#x}, size: {}",
+            target_base, span
+        );
+
+        let mut data = vec![0u8; span as usize];
+        let mut missing = MissingRanges::new();
+
+        for phdr in phdrs.iter().filter(|p| p.is_load() && p.p_memsz > 0) {
+            let offset = phdr.p_vaddr - min_vaddr;
+            let dest = data
+                .get_mut(offset as usize..)
+                .and_then(|rest| rest.get_mut(..phdr.p_memsz as usize));
+            let (Some(address), Some(dest)) = (target_base.checked_add(offset), dest) else {
+                eprintln!(
+                    "[!] Skipping PT_LOAD vaddr: {:#x}, memsz: {:#x} outside the image",
+                    phdr.p_vaddr, phdr.p_memsz
+                );
+                continue;
+            };
+            println!(
+                "[*] PT_LOAD vaddr: {:#x}, memsz: {:#x}, addr: {:#x}",
+                phdr.p_vaddr, phdr.p_memsz, address
+            );
+
+            let (segment, segment_missing) = self.reader.read_tolerant(address, dest.len());
+            dest.copy_from_slice(&segment);
+            missing.extend(segment_missing);
+        }
+
+        self.save_dump(so_name, target_base, &data, &missing)
+    }
+
+    fn save_dump(
+        &self,
+        so_name: &str,
+        target_base: u64,
+        data: &[u8],
+        missing: &[(u64, u64)],
+    ) -> Result<PathBuf> {
+        let so_size = data.len();
+        let base_name = Path::new(so_name)
             .file_stem()
             .unwrap_or_default()
             .to_string_lossy();
@@ -245,10 +785,33 @@ impl SoDumper {
         let output_path = self.output_dir.join(&output_filename);
 
         let mut output_file = File::create(&output_path)?;
-        output_file.write_all(&data)?;
+        output_file.write_all(data)?;
 
         println!("[+] SO dumped to: {}", output_path.display());
 
+        if !missing.is_empty() {
+            let report = write_missing_report(&output_path, missing)?;
+            println!(
+                "[!] Zero-filled {} unreadable ranges, report: {}",
+                missing.len(),
+                report.display()
+            );
+        }
+
+        if let Err(e) = self.write_entropy_report(target_base, &output_path, data) {
+            eprintln!("[!] Entropy analysis failed: {}", e);
+        }
+
+        if let Err(e) = self.write_symbol_map(target_base, &output_path, data) {
+            eprintln!("[!] Symbol map failed: {}", e);
+        }
+
+        if self.jni_scan {
+            if let Err(e) = self.write_jni_report(target_base, &output_path, data) {
+                eprintln!("[!] JNI scan failed: {}", e);
+            }
+        }
+
         if self.auto_fix {
             if let Err(e) = self.auto_fix_so(target_base, &output_path) {
                 eprintln!("[!] Auto-fix failed: {}, but SO dump succeeded", e);
@@ -258,6 +821,143 @@ impl SoDumper {
         Ok(output_path)
     }
 
+    /// 计算各段和每页的熵并与磁盘文件对比，打印加壳结论，逐页结果写入`.entropy.txt`
+    fn write_entropy_report(&self, target_base: u64, so_path: &Path, data: &[u8]) -> Result<()> {
+        let disk = self
+            .parse_proc_maps()?
+            .into_iter()
+            .find(|m| target_base >= m.start && target_base < m.end && m.pathname.starts_with('/'))
+            .and_then(|m| self.read_mapped_file(&m).ok())
+            .map(|(_, buffer)| buffer);
+        let report = analyze(data, disk.as_deref())?;
+
+        let format_disk = |entropy: Option<f64>| {
+            entropy
+                .map(|e| format!("{:.2}", e))
+                .unwrap_or_else(|| "-".to_string())
+        };
+        let mut text = String::new();
+        text.push_str("# vaddr entropy disk_entropy\n");
+        for segment in &report.segments {
+            println!(
+                "[+] Segment {:#x} {} size {:#x}: entropy {:.2}, disk {}, {}/{} high-entropy pages",
+                segment.vaddr,
+                segment.permissions(),
+                segment.memsz,
+                segment.entropy,
+                format_disk(segment.disk_entropy),
+                segment.high_pages(),
+                segment.pages.len()
+            );
+            for page in &segment.pages {
+                text.push_str(&format!(
+                    "{:#x} {:.2} {}\n",
+                    page.vaddr,
+                    page.entropy,
+                    format_disk(page.disk_entropy)
+                ));
+            }
+        }
+        for signature in &report.signatures {
+            println!("[!] Packer signature: {}", signature);
+        }
+        if disk.is_none() {
+            println!("[*] On-disk file not available, entropy not compared");
+        }
+        println!("[+] Packer verdict: {}", report.verdict);
+
+        let report_path = so_path.with_extension("entropy.txt");
+        fs::write(&report_path, text)?;
+        println!("[+] Page entropy written to: {}", report_path.display());
+        Ok(())
+    }
+
+    /// 在dump文件旁写入导出符号表`.sym`和`.json`
+    fn write_symbol_map(&self, target_base: u64, so_path: &Path, data: &[u8]) -> Result<()> {
+        let symbols = self.sofixer.extract_symbols(data, target_base)?;
+
+        let mut sym = String::new();
+        for s in &symbols {
+            sym.push_str(&format!(
+                "{:016x} {:8x} {:<7} {:<6} {}\n",
+                s.address, s.size, s.sym_type, s.bind, s.name
+            ));
+        }
+        let sym_path = so_path.with_extension("sym");
+        fs::write(&sym_path, sym)?;
+
+        let objects: Vec<String> = symbols
+            .iter()
+            .map(|s| {
+                format!(
+                    "  {{\"name\": {}, \"address\": \"{:#x}\", \"value\": \"{:#x}\", \
+                     \"size\": {}, \"type\": \"{}\", \"bind\": \"{}\"}}",
+                    json_string(&s.name),
+                    s.address,
+                    s.value,
+                    s.size,
+                    s.sym_type,
+                    s.bind
+                )
+            })
+            .collect();
+        let json_path = so_path.with_extension("json");
+        fs::write(&json_path, format!("[\n{}\n]\n", objects.join(",\n")))?;
+
+        println!(
+            "[+] {} symbols written to: {}, {}",
+            symbols.len(),
+            sym_path.display(),
+            json_path.display()
+        );
+        Ok(())
+    }
+
+    /// 报告JNINativeMethod表以及JNI_OnLoad、Java_*导出，写入`.jni.txt`
+    fn write_jni_report(&self, target_base: u64, so_path: &Path, data: &[u8]) -> Result<()> {
+        let methods = scan_native_methods(data, target_base)?;
+        let exports: Vec<DynSymbol> = self
+            .sofixer
+            .extract_symbols(data, target_base)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|s| s.name == "JNI_OnLoad" || s.name.starts_with("Java_"))
+            .collect();
+
+        let mut report = String::new();
+        report.push_str("# RegisterNatives tables: table name signature function\n");
+        for method in &methods {
+            report.push_str(&format!(
+                "{:#x} {} {} {:#x}\n",
+                method.table, method.name, method.signature, method.function
+            ));
+        }
+        report.push_str("# JNI exports: address name\n");
+        for export in &exports {
+            report.push_str(&format!("{:#x} {}\n", export.value, export.name));
+        }
+
+        for method in &methods {
+            println!(
+                "[+] JNI native {}{} -> {:#x}",
+                method.name, method.signature, method.function
+            );
+        }
+        for export in &exports {
+            println!("[+] JNI export {} -> {:#x}", export.name, export.value);
+        }
+
+        let report_path = so_path.with_extension("jni.txt");
+        fs::write(&report_path, report)?;
+        println!(
+            "[+] {} native methods, {} JNI exports written to: {}",
+            methods.len(),
+            exports.len(),
+            report_path.display()
+        );
+        Ok(())
+    }
+
     fn auto_fix_so(&self, target_base: u64, so_path: &Path) -> Result<()> {
         let so_name = so_path
             .file_name()
@@ -269,8 +969,6 @@ impl SoDumper {
 
         println!("[+] Auto-fixing SO file: {}", so_name);
 
-        self.sofixer.extract()?;
-
         self.sofixer.fix_so(
             target_base,
             &so_path.to_string_lossy(),
@@ -280,75 +978,1573 @@ impl SoDumper {
         Ok(())
     }
 
-    pub fn dump(&self) -> Result<()> {
-        println!(
-            "[+] Starting SO dump process for target: {}",
-            self.target_name
-        );
-        println!("[+] Target PID: {}", self.target_pid);
-        println!("[+] Architecture: 64-bit only (ARM64/x86_64)");
+    fn read_solist(&self, solist_offset: u64) -> Result<SoList> {
+        let linker_base = self.get_linker_base()?;
+        progress!(self, "[+] {} base: {:#x}", self.linker_name(), linker_base);
 
-        if self.auto_fix {
-            println!("[+] Extracting SoFixer binary...");
-            self.extract_sofixer()?;
+        let solist_addr = linker_base + solist_offset;
+        progress!(self, "[+] solist addr: {:#x}", solist_addr);
+
+        let solist_head = self.get_solist_head(solist_addr)?;
+        let layout = self.select_layout(solist_head)?;
+
+        let mut namespace_members = Vec::new();
+        if self.namespaces {
+            match self.read_namespaces(layout) {
+                Ok(namespaces) => {
+                    for namespace in namespaces {
+                        namespace_members.extend(namespace.members);
+                    }
+                    namespace_members.sort_unstable();
+                    namespace_members.dedup();
+                }
+                Err(e) => eprintln!("[!] Failed to walk linker namespaces: {}", e),
+            }
         }
 
-        let solist_offset = self.get_solist_offset()?;
-        println!("[+] solist offset: {:#x}", solist_offset);
+        Ok(SoList {
+            head: solist_head,
+            layout,
+            namespace_members,
+        })
+    }
 
-        self.stop_process()?;
+    // libc++的std::string：最低位为0时是短字符串，长度在首字节高7位
+    fn read_std_string(&self, address: u64) -> Result<String> {
+        const MAX_LENGTH: u64 = 0x400;
+        let ptr_size = self.class.ptr_size();
+        let data = self.read_process_memory(address, ptr_size * 3)?;
+
+        if data[0] & 1 == 0 {
+            let len = (data[0] >> 1) as usize;
+            let bytes = data
+                .get(1..1 + len)
+                .ok_or_else(|| anyhow!("Invalid std::string at {:#x}", address))?;
+            return Ok(String::from_utf8_lossy(bytes).into_owned());
+        }
 
-        let result = (|| -> Result<()> {
-            let linker_base = self.get_linker_base()?;
-            println!("[+] linker64 base: {:#x}", linker_base);
+        let size = self.read_pointer(&data, ptr_size)?;
+        let pointer = self.read_pointer(&data, ptr_size * 2)?;
+        if size > MAX_LENGTH {
+            return Err(anyhow!("Invalid std::string at {:#x}", address));
+        }
+        let bytes = self.read_process_memory(pointer, size as usize)?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
 
-            let (target_base, target_end) = self.get_target_mapping()?;
-            let target_size = target_end - target_base;
-            println!(
-                "[+] target base: {:#x}, end: {:#x}, size: {}",
-                target_base, target_end, target_size
-            );
+    // LinkedList<soinfo>为{ head_, tail_ }，节点为{ next, element }，尾节点必须等于tail_
+    fn read_soinfo_list(
+        &self,
+        list_addr: u64,
+        layout: &SoInfoLayout,
+        mappings: &[MemoryMapping],
+    ) -> Option<Vec<u64>> {
+        const MAX_MEMBERS: usize = 4096;
+        let ptr_size = self.class.ptr_size();
+        let data = self.read_process_memory(list_addr, ptr_size * 2).ok()?;
+        let head = self.read_pointer(&data, 0).ok()?;
+        let tail = self.read_pointer(&data, ptr_size).ok()?;
+        if head == 0 {
+            return None;
+        }
+
+        let mut members = Vec::new();
+        let mut entry = head;
+        let mut last = 0;
+        while entry != 0 && members.len() < MAX_MEMBERS {
+            let node = self.read_process_memory(entry, ptr_size * 2).ok()?;
+            let next = self.read_pointer(&node, 0).ok()?;
+            let element = self.read_pointer(&node, ptr_size).ok()?;
+
+            let soinfo = self.parse_soinfo(element, layout).ok()?;
+            if !Self::is_placeholder_soinfo(&soinfo) && !Self::is_valid_soinfo(&soinfo, mappings) {
+                return None;
+            }
+            members.push(element);
+            last = entry;
+            entry = next;
+        }
 
-            let solist_addr = linker_base + solist_offset;
-            println!("[+] solist addr: {:#x}", solist_addr);
+        (last == tail).then_some(members)
+    }
 
-            let solist_head = self.get_solist_head(solist_addr)?;
+    fn read_namespace(
+        &self,
+        address: u64,
+        list_offset: u64,
+        layout: &SoInfoLayout,
+        mappings: &[MemoryMapping],
+    ) -> Option<LinkerNamespace> {
+        const MAX_NAME: usize = 128;
+        let name = self.read_std_string(address).ok()?;
+        if name.is_empty()
+            || name.len() > MAX_NAME
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "_-.:/()".contains(c))
+        {
+            return None;
+        }
 
-            let so_size = match self.find_target_soinfo(solist_head, target_base) {
-                Ok(size) => {
-                    if size > target_size * 10 {
-                        println!("[*] soinfo size too large, using target_size");
-                        target_size
-                    } else {
-                        size
-                    }
+        let members = self.read_soinfo_list(address + list_offset, layout, mappings)?;
+        Some(LinkerNamespace {
+            address,
+            name,
+            members,
+        })
+    }
+
+    /// 从g_default_namespace开始，在linker分配的内存中搜索其它android_namespace_t
+    fn read_namespaces(&self, layout: &SoInfoLayout) -> Result<Vec<LinkerNamespace>> {
+        const MAX_LIST_OFFSET: u64 = 0x200;
+        let linker_base = self.get_linker_base()?;
+        let default_namespace =
+            linker_base + self.find_linker_symbol("__dl_g_default_namespace")?;
+        let mappings = self.parse_proc_maps()?;
+        let ptr_size = self.class.ptr_size() as u64;
+
+        // soinfo_list_在namespace中的偏移随版本变化，在g_default_namespace上探测
+        let list_offset = (ptr_size..MAX_LIST_OFFSET)
+            .step_by(ptr_size as usize)
+            .find(|offset| {
+                self.read_soinfo_list(default_namespace + offset, layout, &mappings)
+                    .is_some()
+            })
+            .ok_or_else(|| anyhow!("Could not find soinfo_list_ in g_default_namespace"))?;
+        progress!(
+            self,
+            "[+] g_default_namespace: {:#x}, soinfo_list_ offset: {:#x}",
+            default_namespace,
+            list_offset
+        );
+
+        let mut namespaces = vec![self
+            .read_namespace(default_namespace, list_offset, layout, &mappings)
+            .ok_or_else(|| anyhow!("Invalid g_default_namespace"))?];
+
+        let allocations: Vec<&MemoryMapping> = mappings
+            .iter()
+            .filter(|m| m.pathname.starts_with("[anon:linker_alloc"))
+            .collect();
+        for mapping in &allocations {
+            let (data, _) = self
+                .reader
+                .read_tolerant(mapping.start, (mapping.end - mapping.start) as usize);
+            let list_end = (list_offset + ptr_size * 2) as usize;
+
+            for offset in (0..data.len().saturating_sub(list_end)).step_by(ptr_size as usize) {
+                let address = mapping.start + offset as u64;
+                if address == default_namespace {
+                    continue;
                 }
-                Err(_) => {
-                    println!("[*] Could not find in soinfo chain, trying backup search method");
-                    match self.search_soinfo_chain(solist_head, target_base) {
-                        Ok(size) => {
-                            if size > target_size * 10 {
-                                println!("[*] backup search size too large, using target_size");
-                                target_size
-                            } else {
-                                size
-                            }
-                        }
-                        Err(_) => {
-                            println!("[*] Backup search failed, using target_size");
-                            target_size
-                        }
-                    }
+                // 链表节点同样由linker分配，先过滤head_
+                let head = self.read_pointer(&data, offset + list_offset as usize)?;
+                if !allocations.iter().any(|m| head >= m.start && head < m.end) {
+                    continue;
                 }
-            };
+                if let Some(namespace) =
+                    self.read_namespace(address, list_offset, layout, &mappings)
+                {
+                    namespaces.push(namespace);
+                }
+            }
+        }
 
-            let _dump_path = self.dump_so(target_base, so_size)?;
+        Ok(namespaces)
+    }
 
-            Ok(())
-        })();
+    /// 打印所有linker namespace及其成员
+    pub fn list_namespaces(&self) -> Result<()> {
+        let solist_offset = self.find_solist_offset()?;
+        let linker_base = self.get_linker_base()?;
+        let solist_head = self.get_solist_head(linker_base + solist_offset)?;
+        let layout = self.select_layout(solist_head)?;
+        let mappings = self.parse_proc_maps()?;
+
+        let namespaces = self.read_namespaces(layout)?;
+        println!("[+] Found {} linker namespaces:", namespaces.len());
+        for namespace in &namespaces {
+            println!(
+                "[+] namespace {} at {:#x}, {} members",
+                namespace.name,
+                namespace.address,
+                namespace.members.len()
+            );
+            for &member in &namespace.members {
+                let soinfo = self.parse_soinfo(member, layout)?;
+                let path = mappings
+                    .iter()
+                    .find(|m| m.start == soinfo.base)
+                    .map(|m| m.pathname.as_str())
+                    .unwrap_or("");
+                println!("    {:#x} {:#018x} {}", member, soinfo.base, path);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn resolve_so_size(&self, solist: Option<&SoList>, target_base: u64, target_size: u64) -> u64 {
+        // link_map中没有映射大小，直接使用程序头
+        let Some(solist) = solist else {
+            return match self.phdr_size(target_base) {
+                Ok(size) if size <= target_size * 10 => size,
+                _ => {
+                    println!("[*] Program header search failed, using target_size");
+                    target_size
+                }
+            };
+        };
+
+        match self.find_target_soinfo(solist, target_base) {
+            Ok(size) => {
+                if size > target_size * 10 {
+                    println!("[*] soinfo size too large, using target_size");
+                    target_size
+                } else {
+                    size
+                }
+            }
+            Err(_) => {
+                println!("[*] Could not find in soinfo chain, trying backup search method");
+                match self.search_soinfo_chain(solist.head, target_base) {
+                    Ok(size) => {
+                        if size > target_size * 10 {
+                            println!("[*] backup search size too large, using target_size");
+                            target_size
+                        } else {
+                            size
+                        }
+                    }
+                    Err(_) => {
+                        println!("[*] Backup search failed, trying program headers");
+                        match self.phdr_size(target_base) {
+                            Ok(size) if size <= target_size * 10 => size,
+                            _ => {
+                                println!("[*] Program header search failed, using target_size");
+                                target_size
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // 沿solist收集所有soinfo，启用namespace时合并各namespace成员
+    fn collect_soinfos(&self, solist: &SoList) -> Vec<(u64, SoInfo)> {
+        const MAX_ITERATIONS: usize = 1000;
+        let mut addresses = Vec::new();
+        let mut current = solist.head;
+
+        while current != 0 && addresses.len() < MAX_ITERATIONS {
+            let Ok(soinfo) = self.parse_soinfo(current, solist.layout) else {
+                break;
+            };
+            addresses.push(current);
+            current = soinfo.next;
+        }
+        addresses.extend(&solist.namespace_members);
+        addresses.sort_unstable();
+        addresses.dedup();
+
+        addresses
+            .into_iter()
+            .filter_map(|address| {
+                let soinfo = self.parse_soinfo(address, solist.layout).ok()?;
+                (!Self::is_placeholder_soinfo(&soinfo)).then_some((address, soinfo))
+            })
+            .collect()
+    }
+
+    // soname取自内存中动态段的DT_SONAME
+    fn read_soinfo_soname(&self, soinfo: &SoInfo) -> Option<String> {
+        if soinfo.dynamic == 0 || soinfo.strtab == 0 {
+            return None;
+        }
+        let (data, _) = self
+            .reader
+            .read_tolerant(soinfo.dynamic, PAGE_SIZE as usize);
+        let soname = parse_dynamic(&data, self.class)
+            .into_iter()
+            .find(|d| d.d_tag == DT_SONAME)?;
+        let (name, _) = self
+            .reader
+            .read_tolerant(soinfo.strtab + soname.d_val, 0x100);
+        read_cstr(&name, 0)
+    }
+
+    // realpath_不可用或不像路径时回退到maps中的文件名
+    fn read_soinfo_realpath(
+        &self,
+        address: u64,
+        soinfo: &SoInfo,
+        layout: &SoInfoLayout,
+        mappings: &[MemoryMapping],
+    ) -> String {
+        let realpath = layout
+            .realpath
+            .and_then(|offset| self.read_std_string(address + offset as u64).ok())
+            .filter(|path| path.starts_with('/') || path.starts_with('['));
+
+        realpath.unwrap_or_else(|| {
+            mappings
+                .iter()
+                .find(|m| m.start == soinfo.base)
+                .map(|m| m.pathname.clone())
+                .unwrap_or_default()
+        })
+    }
+
+    fn collect_soinfo_entries(&self) -> Result<Vec<(u64, SoInfo, String, String)>> {
+        let solist_offset = self.find_solist_offset()?;
+        self.stop_process()?;
+
+        let result = (|| -> Result<Vec<(u64, SoInfo, String, String)>> {
+            let solist = self.read_solist(solist_offset)?;
+            let mappings = self.parse_proc_maps()?;
+
+            Ok(self
+                .collect_soinfos(&solist)
+                .into_iter()
+                .map(|(address, soinfo)| {
+                    let soname = self.read_soinfo_soname(&soinfo).unwrap_or_default();
+                    let realpath =
+                        self.read_soinfo_realpath(address, &soinfo, solist.layout, &mappings);
+                    (address, soinfo, soname, realpath)
+                })
+                .collect())
+        })();
+
+        self.continue_process()?;
+        result
+    }
+
+    /// 解码所有soinfo的主要字段，以表格或JSON输出到stdout
+    pub fn inspect_soinfo(&self) -> Result<()> {
+        let entries = self.collect_soinfo_entries()?;
+
+        if self.json {
+            let objects: Vec<String> = entries
+                .iter()
+                .map(|(address, s, soname, realpath)| {
+                    format!(
+                        "  {{\"soinfo\": \"{:#x}\", \"realpath\": {}, \"soname\": {}, \
+                         \"base\": \"{:#x}\", \"size\": {}, \"load_bias\": \"{:#x}\", \
+                         \"phdr\": \"{:#x}\", \"phnum\": {}, \"dynamic\": \"{:#x}\", \
+                         \"strtab\": \"{:#x}\", \"symtab\": \"{:#x}\", \
+                         \"init_array\": \"{:#x}\", \"init_array_count\": {}, \
+                         \"fini_array\": \"{:#x}\", \"fini_array_count\": {}, \
+                         \"flags\": \"{:#x}\", \"constructors_called\": {}}}",
+                        address,
+                        json_string(realpath),
+                        json_string(soname),
+                        s.base,
+                        s.size,
+                        s.load_bias,
+                        s.phdr,
+                        s.phnum,
+                        s.dynamic,
+                        s.strtab,
+                        s.symtab,
+                        s.init_array,
+                        s.init_array_count,
+                        s.fini_array,
+                        s.fini_array_count,
+                        s.flags,
+                        s.constructors_called
+                    )
+                })
+                .collect();
+            println!("[\n{}\n]", objects.join(",\n"));
+            eprintln!("[+] Found {} soinfo", entries.len());
+            return Ok(());
+        }
+
+        println!("[+] Found {} soinfo:", entries.len());
+        println!(
+            "{:<14} {:<14} {:<10} {:<14} {:<14} {:<5} {:<14} {:<14} {:<14} {:<18} {:<18} {:<10} {:<5} {:<24} Realpath",
+            "Soinfo", "Base", "Size", "LoadBias", "Phdr", "Phnum", "Dynamic", "Strtab", "Symtab",
+            "InitArray", "FiniArray", "Flags", "Ctor", "Soname"
+        );
+        println!("{:-<220}", "");
+        for (address, s, soname, realpath) in &entries {
+            println!(
+                "{:<14x} {:<14x} {:<10x} {:<14x} {:<14x} {:<5} {:<14x} {:<14x} {:<14x} {:<18} {:<18} {:<10x} {:<5} {:<24} {}",
+                address,
+                s.base,
+                s.size,
+                s.load_bias,
+                s.phdr,
+                s.phnum,
+                s.dynamic,
+                s.strtab,
+                s.symtab,
+                format!("{:x}({})", s.init_array, s.init_array_count),
+                format!("{:x}({})", s.fini_array, s.fini_array_count),
+                s.flags,
+                s.constructors_called,
+                soname,
+                realpath
+            );
+        }
+
+        Ok(())
+    }
+
+    /// 解码目标SO的所有重定位表（REL/RELA/APS2/RELR）并打印
+    pub fn print_relocs(&self) -> Result<()> {
+        self.stop_process()?;
+
+        let result = (|| -> Result<(u64, Vec<u8>)> {
+            let (target_base, target_end) = self.get_target_mapping()?;
+            let size = self
+                .phdr_size(target_base)
+                .unwrap_or(target_end - target_base);
+            let (data, _) = self.reader.read_tolerant(target_base, size as usize);
+            Ok((target_base, data))
+        })();
+
+        self.continue_process()?;
+        let (target_base, data) = result?;
+        let machine = ElfHeader::parse(&data)?.e_machine;
+        let relocs = self.sofixer.decode_relocs(&data, target_base)?;
+
+        println!(
+            "[+] Found {} relocations in {}:",
+            relocs.len(),
+            self.target_name
+        );
+        println!(
+            "{:<18} {:<28} {:<12} Symbol + Addend",
+            "Offset", "Type", "Format"
+        );
+        println!("{:-<100}", "");
+        for (reloc, name) in &relocs {
+            println!(
+                "{:<18x} {:<28} {:<12} {} + {:#x}",
+                reloc.r_offset,
+                r_to_str(reloc.r_type, machine),
+                format!("{:?}", reloc.format),
+                name.as_deref().unwrap_or(""),
+                reloc.r_addend
+            );
+        }
+
+        Ok(())
+    }
+
+    /// 对比目标SO的文件映射段与磁盘文件，忽略重定位位置和动态段，报告被修改的区间
+    pub fn diff(&self, patched: bool) -> Result<()> {
+        const MERGE_GAP: usize = 16;
+        println!("[+] Memory/disk diff for {}", self.target_name);
+        println!("[+] Target PID: {}", self.target_pid);
+
+        self.stop_process()?;
+
+        let result = (|| -> Result<(u64, Vec<u8>, Vec<ModifiedRange>)> {
+            let (target_base, target_end) = self.get_target_mapping()?;
+            let mappings = self.parse_proc_maps()?;
+            let pathname = mappings
+                .iter()
+                .find(|m| target_base >= m.start && target_base < m.end)
+                .map(|m| m.pathname.clone())
+                .filter(|p| p.starts_with('/'))
+                .ok_or_else(|| anyhow!("{:#x} is not a file-backed mapping", target_base))?;
+            let segments: Vec<&MemoryMapping> =
+                mappings.iter().filter(|m| m.pathname == pathname).collect();
+
+            let (disk_path, disk) = self.read_mapped_file(segments[0])?;
+            println!("[+] On-disk image: {}", disk_path);
+            let header = ElfHeader::parse(&disk)?;
+            let phdrs = parse_program_headers(&disk, &header)?;
+            let symbols = SymbolIndex::from_file(&disk);
+
+            // 重定位位置和动态段由linker写入，属于预期修改
+            let size = self
+                .phdr_size(target_base)
+                .unwrap_or(target_end - target_base);
+            let (image, _) = self.reader.read_tolerant(target_base, size as usize);
+            let mut sites: Vec<u64> = match self.sofixer.decode_relocs(&image, target_base) {
+                Ok(relocs) => relocs.iter().map(|(r, _)| r.r_offset).collect(),
+                Err(e) => {
+                    eprintln!("[!] Failed to decode relocations: {}", e);
+                    Vec::new()
+                }
+            };
+            sites.sort_unstable();
+            sites.dedup();
+            println!("[+] Ignoring {} relocation sites", sites.len());
+            let ptr_size = header.class.ptr_size() as u64;
+            let dynamic = phdrs
+                .iter()
+                .find(|p| p.p_type == PT_DYNAMIC)
+                .map(|p| p.p_vaddr..p.p_vaddr + p.p_memsz);
+            let expected = |vaddr: u64| {
+                let index = sites.partition_point(|site| *site <= vaddr);
+                index > 0 && vaddr < sites[index - 1] + ptr_size
+                    || dynamic.as_ref().is_some_and(|d| d.contains(&vaddr))
+            };
+
+            let mut modified = Vec::new();
+            let mut patched_image = disk.clone();
+            for segment in &segments {
+                let segment_end = segment.offset + (segment.end - segment.start);
+                // 只对比PT_LOAD中有文件内容的部分，.bss所在页的尾部不在磁盘上
+                for phdr in phdrs.iter().filter(|p| p.is_load()) {
+                    let start = phdr.p_offset.max(segment.offset);
+                    let end = (phdr.p_offset + phdr.p_filesz)
+                        .min(segment_end)
+                        .min(disk.len() as u64);
+                    if start >= end {
+                        continue;
+                    }
+
+                    let address = segment.start + (start - segment.offset);
+                    let vaddr = phdr.p_vaddr + (start - phdr.p_offset);
+                    let (memory, missing) =
+                        self.reader.read_tolerant(address, (end - start) as usize);
+                    let unreadable = |i: usize| {
+                        let a = address + i as u64;
+                        missing.iter().any(|(s, e)| a >= *s && a < *e)
+                    };
+                    let on_disk = &disk[start as usize..end as usize];
+                    let ranges = diff_bytes(on_disk, &memory, &|i| {
+                        unreadable(i) || expected(vaddr + i as u64)
+                    });
+
+                    for range in merge_ranges(&ranges, MERGE_GAP) {
+                        modified.push(ModifiedRange {
+                            address: address + range.start as u64,
+                            vaddr: vaddr + range.start as u64,
+                            file_offset: start + range.start as u64,
+                            size: range.len() as u64,
+                            permissions: segment.permissions.clone(),
+                            symbol: symbols.lookup(vaddr + range.start as u64),
+                        });
+                    }
+                    for range in ranges {
+                        let offset = start as usize + range.start;
+                        patched_image[offset..offset + range.len()].copy_from_slice(&memory[range]);
+                    }
+                }
+            }
+
+            Ok((target_base, patched_image, modified))
+        })();
+
+        self.continue_process()?;
+        let (target_base, patched_image, modified) = result?;
+
+        let base_name = Path::new(&self.target_name)
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        let mut report = String::new();
+        report.push_str("# address vaddr file_offset size permissions symbol\n");
+        for range in &modified {
+            let symbol = range.symbol.as_deref().unwrap_or("");
+            println!(
+                "[!] Modified {:#x} vaddr {:#x} offset {:#x} size {:#x} {} {}",
+                range.address,
+                range.vaddr,
+                range.file_offset,
+                range.size,
+                range.permissions,
+                symbol
+            );
+            report.push_str(&format!(
+                "{:#x} {:#x} {:#x} {:#x} {} {}\n",
+                range.address,
+                range.vaddr,
+                range.file_offset,
+                range.size,
+                range.permissions,
+                symbol
+            ));
+        }
+
+        let report_path = self
+            .output_dir
+            .join(format!("{}_{:#x}_diff.txt", base_name, target_base));
+        fs::write(&report_path, report)?;
+        println!(
+            "[+] {} modified ranges ({} bytes) written to: {}",
+            modified.len(),
+            modified.iter().map(|r| r.size).sum::<u64>(),
+            report_path.display()
+        );
+
+        if patched {
+            let patched_path = self
+                .output_dir
+                .join(format!("{}_{:#x}_patched.so", base_name, target_base));
+            fs::write(&patched_path, &patched_image)?;
+            println!("[+] Patched file written to: {}", patched_path.display());
+        }
+
+        Ok(())
+    }
+
+    // 按soinfo链或link_map收集已加载库的基址和路径
+    fn collect_modules(
+        &self,
+        solist_offset: Option<u64>,
+        mappings: &[MemoryMapping],
+    ) -> Result<Vec<(u64, String)>> {
+        let mut modules: Vec<(u64, String)> = match solist_offset {
+            Some(offset) => {
+                let solist = self.read_solist(offset)?;
+                self.collect_soinfos(&solist)
+                    .into_iter()
+                    .map(|(address, soinfo)| {
+                        let path =
+                            self.read_soinfo_realpath(address, &soinfo, solist.layout, mappings);
+                        (soinfo.base, path)
+                    })
+                    .collect()
+            }
+            None => list_link_map(self.target_pid, self.reader.as_ref(), self.class)?
+                .into_iter()
+                .map(|so| (so.start, so.path))
+                .collect(),
+        };
+        modules.retain(|(base, _)| *base != 0);
+        modules.sort_by_key(|(base, _)| *base);
+        modules.dedup_by_key(|(base, _)| *base);
+        Ok(modules)
+    }
+
+    /// 对比各库GOT槽位与导出库中的真实地址，并检查导出函数开头的跳转指令
+    pub fn report_hooks(&self) -> Result<()> {
+        const PROLOGUE_SIZE: usize = 32;
+        println!("[+] Hook report");
+        println!("[+] Target PID: {}", self.target_pid);
+
+        let solist_offset = if self.link_map {
+            None
+        } else {
+            Some(self.find_solist_offset()?)
+        };
+        self.stop_process()?;
+
+        let result = (|| -> Result<()> {
+            let mappings = self.parse_proc_maps()?;
+            let owner = |address: u64| {
+                mappings
+                    .iter()
+                    .find(|m| address >= m.start && address < m.end)
+                    .map(|m| match m.pathname.as_str() {
+                        "" => "[anonymous]".to_string(),
+                        path => path.to_string(),
+                    })
+                    .unwrap_or_else(|| "<unmapped>".to_string())
+            };
+
+            let mut images = Vec::new();
+            for (base, path) in self.collect_modules(solist_offset, &mappings)? {
+                let Ok((header, phdrs)) = self.read_elf_phdrs(base) else {
+                    continue;
+                };
+                let size = load_span(&phdrs);
+                let (image, _) = self.reader.read_tolerant(base, size as usize);
+                let symbols = self
+                    .sofixer
+                    .extract_symbols(&image, base)
+                    .unwrap_or_default();
+                images.push((base, path, header, phdrs, image, symbols));
+            }
+
+            // 同名符号可能由多个库导出，IFUNC的GOT值是解析函数的返回值，不参与对比
+            let mut exports: HashMap<&str, Vec<u64>> = HashMap::new();
+            let mut ifuncs = HashSet::new();
+            for (_, _, _, _, _, symbols) in &images {
+                for symbol in symbols {
+                    if symbol.sym_type == "IFUNC" {
+                        ifuncs.insert(symbol.name.as_str());
+                    }
+                    exports
+                        .entry(symbol.name.as_str())
+                        .or_default()
+                        .push(symbol.address);
+                }
+            }
+            println!(
+                "[+] {} libraries, {} exported symbols",
+                images.len(),
+                exports.len()
+            );
+
+            let (mut slots, mut functions, mut got_hooks, mut inline_hooks) = (0, 0, 0, 0);
+            for (base, path, header, phdrs, image, symbols) in &images {
+                let min_vaddr = min_load_vaddr(phdrs);
+                let own = |address: u64| address >= *base && address < base + image.len() as u64;
+                // glibc延迟绑定时JUMP_SLOT仍为磁盘文件中的初值，即PLT桩地址加上加载偏移
+                let disk = OnceCell::new();
+                let lazy_binding = |r_offset: u64| -> Option<u64> {
+                    let disk = disk
+                        .get_or_init(|| {
+                            let mapping = mappings.iter().find(|m| m.start == *base)?;
+                            self.read_mapped_file(mapping).ok().map(|(_, data)| data)
+                        })
+                        .as_deref()?;
+                    let header = ElfHeader::parse(disk).ok()?;
+                    let phdr = parse_program_headers(disk, &header)
+                        .ok()?
+                        .into_iter()
+                        .find(|p| {
+                            p.is_load()
+                                && r_offset >= p.p_vaddr
+                                && r_offset - p.p_vaddr < p.p_filesz
+                        })?;
+                    let value = header
+                        .class
+                        .read_word(disk, (phdr.p_offset + r_offset - phdr.p_vaddr) as usize)?;
+                    Some(value.wrapping_add(base.wrapping_sub(min_vaddr)))
+                };
+
+                let relocs = self.sofixer.decode_relocs(image, *base).unwrap_or_default();
+                for (reloc, name) in &relocs {
+                    let Some(name) = name.as_deref() else {
+                        continue;
+                    };
+                    if !is_got_type(header.e_machine, reloc.r_type) || ifuncs.contains(name) {
+                        continue;
+                    }
+                    let Some(expected) = exports.get(name) else {
+                        continue;
+                    };
+                    let Some(value) = reloc
+                        .r_offset
+                        .checked_sub(min_vaddr)
+                        .and_then(|offset| header.class.read_word(image, offset as usize))
+                    else {
+                        continue;
+                    };
+                    slots += 1;
+
+                    let resolved = expected.iter().any(|address| {
+                        *address == value || address.wrapping_add(reloc.r_addend as u64) == value
+                    });
+                    if value == 0 || resolved || lazy_binding(reloc.r_offset) == Some(value) {
+                        continue;
+                    }
+                    got_hooks += 1;
+                    println!(
+                        "[!] GOT hook in {}: {} slot {:#x} -> {:#x} ({}), expected {:#x}",
+                        path,
+                        name,
+                        base + (reloc.r_offset - min_vaddr),
+                        value,
+                        owner(value),
+                        expected[0]
+                    );
+                }
+
+                let exec: Vec<(u64, u64)> = phdrs
+                    .iter()
+                    .filter(|p| p.is_load() && p.p_flags & PF_X != 0)
+                    .map(|p| (p.p_vaddr, p.p_vaddr + p.p_memsz))
+                    .collect();
+                for symbol in symbols.iter().filter(|s| s.sym_type == "FUNC") {
+                    let vaddr = if header.e_machine == EM_ARM {
+                        symbol.value & !1
+                    } else {
+                        symbol.value
+                    };
+                    if !exec
+                        .iter()
+                        .any(|(start, end)| vaddr >= *start && vaddr < *end)
+                    {
+                        continue;
+                    }
+                    let offset = (vaddr - min_vaddr) as usize;
+                    let Some(code) = image.get(offset..(offset + PROLOGUE_SIZE).min(image.len()))
+                    else {
+                        continue;
+                    };
+                    functions += 1;
+
+                    let Some(target) = decode_trampoline(header.e_machine, code, symbol.address)
+                    else {
+                        continue;
+                    };
+                    if own(target & !1) {
+                        continue;
+                    }
+                    inline_hooks += 1;
+                    println!(
+                        "[!] Inline hook in {}: {} {:#x} -> {:#x} ({})",
+                        path,
+                        symbol.name,
+                        symbol.address,
+                        target,
+                        owner(target & !1)
+                    );
+                }
+            }
+
+            println!(
+                "[+] Checked {} GOT slots and {} exported functions",
+                slots, functions
+            );
+            println!("[+] {} GOT hooks, {} inline hooks", got_hooks, inline_hooks);
+            Ok(())
+        })();
+
+        self.continue_process()?;
+
+        result
+    }
+
+    /// 对比soinfo链和maps中的ELF镜像，找出从solist中摘除或未被映射的模块
+    pub fn report_hidden(&self) -> Result<()> {
+        println!("[+] Hidden library report");
+        println!("[+] Target PID: {}", self.target_pid);
+
+        let solist_offset = self.find_solist_offset()?;
+        self.stop_process()?;
+
+        let result = (|| -> Result<()> {
+            let solist = self.read_solist(solist_offset)?;
+            let soinfos = self.collect_soinfos(&solist);
+            let images = list_elf_images(&self.parse_proc_maps()?, self.reader.as_ref())?;
+            let mappings = self.parse_proc_maps()?;
+            println!(
+                "[+] {} soinfo in chain, {} ELF images in maps",
+                soinfos.len(),
+                images.len()
+            );
+
+            let unlinked: Vec<&SoFileInfo> = images
+                .iter()
+                .filter(|image| !soinfos.iter().any(|(_, s)| s.base == image.start))
+                .collect();
+            println!(
+                "[!] {} ELF images in maps but not in solist:",
+                unlinked.len()
+            );
+            for image in unlinked {
+                let kind = if image.in_memory { "memory" } else { "file" };
+                println!(
+                    "    {:#018x}-{:#018x} {:<6} {}",
+                    image.start, image.end, kind, image.path
+                );
+            }
+
+            let unmapped: Vec<&(u64, SoInfo)> = soinfos
+                .iter()
+                .filter(|(_, s)| !images.iter().any(|image| image.start == s.base))
+                .collect();
+            println!(
+                "[!] {} soinfo without an ELF image in maps:",
+                unmapped.len()
+            );
+            for (address, soinfo) in unmapped {
+                let path = mappings
+                    .iter()
+                    .find(|m| soinfo.base >= m.start && soinfo.base < m.end)
+                    .map(|m| m.pathname.as_str())
+                    .unwrap_or("<unmapped>");
+                println!(
+                    "    soinfo {:#x} base {:#018x} size {:#x} {}",
+                    address, soinfo.base, soinfo.size, path
+                );
+            }
+
+            Ok(())
+        })();
 
         self.continue_process()?;
 
         result
     }
+
+    // DT_INIT和DT_INIT_ARRAY中构造函数的运行时地址，尚未重定位时按重定位项计算
+    fn init_functions(&self, base: u64) -> Result<Vec<u64>> {
+        let (header, phdrs) = self.read_elf_phdrs(base)?;
+        let class = header.class;
+        let min_vaddr = min_load_vaddr(&phdrs);
+        let load_bias = base - min_vaddr;
+        let (image, _) = self.reader.read_tolerant(base, load_span(&phdrs) as usize);
+
+        let dynamic_phdr = phdrs
+            .iter()
+            .find(|p| p.p_type == PT_DYNAMIC)
+            .ok_or_else(|| anyhow!("No PT_DYNAMIC at {:#x}", base))?;
+        let dynamic = parse_dynamic(
+            image
+                .get((dynamic_phdr.p_vaddr - min_vaddr) as usize..)
+                .unwrap_or_default(),
+            class,
+        );
+        let dyn_val = |tag: u64| {
+            dynamic.iter().find(|d| d.d_tag == tag).map(|d| {
+                if d.d_val >= base {
+                    d.d_val - load_bias
+                } else {
+                    d.d_val
+                }
+            })
+        };
+        let relocs = self.sofixer.decode_relocs(&image, base).unwrap_or_default();
+        let mask = match class {
+            ElfClass::Elf32 => u32::MAX as u64,
+            ElfClass::Elf64 => u64::MAX,
+        };
+
+        let mut functions = Vec::new();
+        if let Some(init) = dyn_val(DT_INIT).filter(|v| *v != 0) {
+            functions.push(load_bias + init);
+        }
+        let array = dyn_val(DT_INIT_ARRAY).unwrap_or(0);
+        let count = dyn_val(DT_INIT_ARRAYSZ).unwrap_or(0) / class.ptr_size() as u64;
+        for slot in (0..count).map(|i| array + i * class.ptr_size() as u64) {
+            let Some(value) = slot
+                .checked_sub(min_vaddr)
+                .and_then(|offset| class.read_word(&image, offset as usize))
+            else {
+                continue;
+            };
+            let rela = relocs.iter().find(|(r, _)| {
+                r.r_offset == slot
+                    && matches!(r.format, RelocFormat::Rela | RelocFormat::AndroidRela)
+            });
+            let function = match rela {
+                Some((r, _)) => load_bias.wrapping_add(r.r_addend as u64),
+                None if value == 0 || value == mask => continue,
+                None if value >= base => value,
+                None => load_bias + value,
+            };
+            functions.push(function & mask);
+        }
+
+        Ok(functions)
+    }
+
+    // 在触发函数入口下断点，返回断点地址，没有可用的触发函数时返回None
+    fn arm_trigger(
+        &self,
+        tracer: &mut Tracer,
+        base: u64,
+        trigger: BreakTrigger,
+    ) -> Result<Option<u64>> {
+        let function = match trigger {
+            BreakTrigger::Init => {
+                let functions = self.init_functions(base)?;
+                println!("[+] {} constructors found", functions.len());
+                functions.last().copied()
+            }
+            BreakTrigger::JniOnload => {
+                let (_, phdrs) = self.read_elf_phdrs(base)?;
+                let (image, _) = self.reader.read_tolerant(base, load_span(&phdrs) as usize);
+                let symbol = self
+                    .sofixer
+                    .extract_symbols(&image, base)?
+                    .into_iter()
+                    .find(|s| s.name == "JNI_OnLoad")
+                    .ok_or_else(|| anyhow!("{} does not export JNI_OnLoad", self.target_name))?;
+                Some(symbol.address)
+            }
+        };
+
+        match function {
+            Some(function) => {
+                println!("[+] Breakpoint at {:?} function {:#x}", trigger, function);
+                Ok(Some(tracer.insert_breakpoint(function)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    // 其他线程全部停止后dump目标SO
+    fn dump_traced(&self, tracer: &mut Tracer, tid: Pid) -> Result<PathBuf> {
+        tracer.stop_all(Some(tid))?;
+        let result = (|| -> Result<PathBuf> {
+            let (target_base, target_end) = self.get_target_mapping()?;
+            if self.segment_mode {
+                return self.dump_so_segments(&self.target_name, target_base);
+            }
+            let so_size = self.resolve_so_size(None, target_base, target_end - target_base);
+            self.dump_so(&self.target_name, target_base, so_size)
+        })();
+        tracer.resume_all()?;
+        result
+    }
+
+    /// 用ptrace断点在构造函数执行完或JNI_OnLoad返回时dump目标SO
+    /// 目标未加载时先在r_debug.r_brk下断点，等待linker映射目标后再设置触发断点
+    pub fn dump_on_trigger(&self, trigger: BreakTrigger) -> Result<()> {
+        println!(
+            "[+] Breakpoint trigger {:?} for target: {}",
+            trigger, self.target_name
+        );
+        println!("[+] Target PID: {}", self.target_pid);
+        let machine = self
+            .machine
+            .ok_or_else(|| anyhow!("Could not read target machine"))?;
+
+        let mut tracer = Tracer::attach(self.target_pid, machine)?;
+        println!("[+] Attached to {} threads", tracer.thread_count());
+
+        let mut r_brk = None;
+        let mut rearm = None;
+        let mut entry = None;
+        let mut exit = None;
+        // 进入触发函数的线程和入口处的sp
+        let mut entered = None;
+        match self.get_target_mapping() {
+            Ok(_) if trigger == BreakTrigger::Init => {
+                return Err(anyhow!(
+                    "{} is already loaded, its constructors have run",
+                    self.target_name
+                ));
+            }
+            Ok((base, _)) => entry = self.arm_trigger(&mut tracer, base, trigger)?,
+            Err(_) => {
+                let r_debug = find_r_debug(self.target_pid, self.reader.as_ref())?;
+                let ptr_size = self.class.ptr_size();
+                let data = self.read_process_memory(r_debug + ptr_size as u64 * 2, ptr_size)?;
+                let address = self.read_pointer(&data, 0)?;
+                println!(
+                    "[+] r_brk: {:#x}, waiting for {} to load",
+                    address, self.target_name
+                );
+                r_brk = Some(tracer.insert_breakpoint(address)?);
+            }
+        }
+        if entry.is_none() && r_brk.is_none() {
+            return Err(anyhow!("No {:?} function to break on", trigger));
+        }
+        tracer.resume_all()?;
+
+        loop {
+            let (tid, address) = match tracer.wait_event()? {
+                TraceEvent::Breakpoint { tid, address } => (tid, address),
+                TraceEvent::Syscall { tid, .. } => {
+                    tracer.resume(tid)?;
+                    continue;
+                }
+                TraceEvent::Exited => {
+                    return Err(anyhow!(
+                        "Process {} exited before the trigger",
+                        self.target_pid
+                    ))
+                }
+            };
+            tracer.remove_breakpoint(address)?;
+
+            if Some(address) == r_brk {
+                // r_brk在映射前后各调用一次，未映射时在返回处重新下断点
+                match self.get_target_mapping() {
+                    Ok((base, _)) => {
+                        println!("[+] {} mapped at {:#x}", self.target_name, base);
+                        r_brk = None;
+                        entry = self.arm_trigger(&mut tracer, base, trigger)?;
+                        if entry.is_none() {
+                            println!("[*] No constructors, dumping now");
+                            self.dump_traced(&mut tracer, tid)?;
+                            break;
+                        }
+                    }
+                    Err(_) => rearm = Some(tracer.insert_breakpoint(tracer.return_address(tid)?)?),
+                }
+            } else if Some(address) == rearm {
+                rearm = None;
+                if let Some(r_brk) = r_brk {
+                    tracer.insert_breakpoint(r_brk)?;
+                }
+            } else if Some(address) == entry {
+                entry = None;
+                let return_address = tracer.return_address(tid)?;
+                println!(
+                    "[+] Thread {} entered trigger function, returns to {:#x}",
+                    tid, return_address
+                );
+                entered = Some((tid, tracer.get_regs(tid)?.sp()));
+                exit = Some(tracer.insert_breakpoint(return_address)?);
+            } else if Some(address) == exit {
+                let returned = match entered {
+                    Some((entry_tid, entry_sp)) if entry_tid == tid => {
+                        tracer.frame_returned(tid, entry_sp)?
+                    }
+                    _ => false,
+                };
+                if returned {
+                    println!("[+] Trigger function returned in thread {}", tid);
+                    self.dump_traced(&mut tracer, tid)?;
+                    break;
+                }
+                // 其他线程或嵌套调用返回到同一地址，单步越过后重新下断点
+                println!(
+                    "[*] Thread {} passed {:#x} from another call, stepping over",
+                    tid, address
+                );
+                tracer.stop_all(Some(tid))?;
+                tracer.single_step(tid)?;
+                tracer.insert_breakpoint(address)?;
+                tracer.resume_all()?;
+            }
+
+            tracer.resume(tid)?;
+        }
+
+        tracer.detach()
+    }
+
+    /// 从address所在页向前搜索连续映射中的ELF头，返回覆盖address的镜像名称、基址和大小
+    fn find_containing_elf(&self, address: u64) -> Result<Option<(String, u64, u64)>> {
+        const MAX_SEARCH_SIZE: u64 = 0x4000000;
+
+        let mappings = self.parse_proc_maps()?;
+        let Some(mut index) = mappings
+            .iter()
+            .position(|m| address >= m.start && address < m.end)
+        else {
+            return Ok(None);
+        };
+        let mut page = address & !(PAGE_SIZE - 1);
+        let limit = page.saturating_sub(MAX_SEARCH_SIZE);
+
+        while page >= limit {
+            if page < mappings[index].start {
+                // 只在首尾相接的映射中搜索
+                if index == 0 || mappings[index - 1].end != mappings[index].start {
+                    break;
+                }
+                index -= 1;
+                continue;
+            }
+            let mapping = &mappings[index];
+            let is_elf = mapping.permissions.starts_with('r')
+                && self
+                    .reader
+                    .read_bytes(page, ELF_MAGIC.len())
+                    .is_ok_and(|magic| magic == ELF_MAGIC);
+            if is_elf {
+                let Some(size) = probe_elf_image(self.reader.as_ref(), page) else {
+                    page = page.saturating_sub(PAGE_SIZE);
+                    continue;
+                };
+                if address >= page + size {
+                    return Ok(None);
+                }
+                let name = match mapping.pathname.rsplit('/').next() {
+                    Some(name) if mapping.pathname.starts_with('/') => name.to_string(),
+                    _ => "anon".to_string(),
+                };
+                return Ok(Some((name, page, size)));
+            }
+            if page == 0 {
+                break;
+            }
+            page -= PAGE_SIZE;
+        }
+
+        Ok(None)
+    }
+
+    // dump可执行区间和所在的ELF，返回写入时间线的记录
+    fn dump_exec_range(&self, sequence: usize, address: u64, size: u64) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        let (data, missing) = self.reader.read_tolerant(address, size as usize);
+        if missing.len() == 1 && missing[0] == (address, address + size) {
+            lines.push(format!(
+                "range {:#x}-{:#x} unreadable",
+                address,
+                address + size
+            ));
+        } else {
+            let path = self
+                .output_dir
+                .join(format!("exec_{}_{:#x}_{}.bin", sequence, address, size));
+            match fs::write(&path, &data) {
+                Ok(()) => {
+                    println!("[+] Executable range dumped to: {}", path.display());
+                    lines.push(format!(
+                        "range {:#x}-{:#x} -> {}",
+                        address,
+                        address + size,
+                        path.display()
+                    ));
+                }
+                Err(e) => lines.push(format!("range {:#x} write failed: {}", address, e)),
+            }
+        }
+
+        match self.find_containing_elf(address) {
+            Ok(Some((name, base, so_size))) => {
+                println!("[+] Range belongs to {} at {:#x}", name, base);
+                // 同一个ELF可能多次触发，按序号区分各次dump
+                let so_name = format!("exec_{}_{}", sequence, name);
+                let result = if self.segment_mode {
+                    self.dump_so_segments(&so_name, base)
+                } else {
+                    self.dump_so(&so_name, base, so_size)
+                };
+                lines.push(match result {
+                    Ok(path) => format!("elf {}@{:#x} -> {}", name, base, path.display()),
+                    Err(e) => format!("elf {}@{:#x} dump failed: {}", name, base, e),
+                });
+            }
+            Ok(None) => lines.push("no containing ELF image".to_string()),
+            Err(e) => lines.push(format!("ELF search failed: {}", e)),
+        }
+
+        lines
+    }
+
+    fn describe_syscall(&self, kind: MapSyscall, call: &Syscall) -> String {
+        let prot = [
+            (libc::PROT_READ, 'r'),
+            (libc::PROT_WRITE, 'w'),
+            (libc::PROT_EXEC, 'x'),
+        ]
+        .iter()
+        .map(|(flag, c)| {
+            if call.args[2] & *flag as u64 != 0 {
+                *c
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>();
+
+        match kind {
+            MapSyscall::Mprotect => format!(
+                "mprotect({:#x}, {:#x}, {}) = {}",
+                call.args[0], call.args[1], prot, call.result
+            ),
+            MapSyscall::Mmap => {
+                let fd = call.args[4] as u32 as i32;
+                let file = (fd >= 0)
+                    .then(|| fs::read_link(format!("/proc/{}/fd/{}", self.target_pid, fd)).ok())
+                    .flatten()
+                    .map(|path| format!(" ({})", path.display()))
+                    .unwrap_or_default();
+                format!(
+                    "mmap({:#x}, {:#x}, {}, {:#x}, {}{}) = {:#x}",
+                    call.args[0], call.args[1], prot, call.args[3], fd, file, call.result
+                )
+            }
+        }
+    }
+
+    /// 跟踪所有线程的mmap/mprotect，映射出可执行内存时在调用返回处dump该区间及其所在的ELF
+    /// 事件时间线写入exec_timeline_<pid>.txt，一直运行到进程退出
+    pub fn trace_exec(&self) -> Result<()> {
+        println!("[+] Exec trace mode");
+        println!("[+] Target PID: {}", self.target_pid);
+        let machine = self
+            .machine
+            .ok_or_else(|| anyhow!("Could not read target machine"))?;
+
+        let timeline_path = self
+            .output_dir
+            .join(format!("exec_timeline_{}.txt", self.target_pid));
+        let mut timeline = File::create(&timeline_path)?;
+        println!("[+] Timeline: {}", timeline_path.display());
+
+        let mut tracer = Tracer::attach(self.target_pid, machine)?;
+        tracer.trace_syscalls()?;
+        println!("[+] Tracing syscalls of {} threads", tracer.thread_count());
+        tracer.resume_all()?;
+
+        let start = Instant::now();
+        let mut count = 0;
+        loop {
+            let (tid, call) = match tracer.wait_event()? {
+                TraceEvent::Syscall { tid, call } => (tid, call),
+                TraceEvent::Breakpoint { tid, .. } => {
+                    tracer.resume(tid)?;
+                    continue;
+                }
+                TraceEvent::Exited => {
+                    writeln!(
+                        timeline,
+                        "[{:>10.3}] process {} exited",
+                        start.elapsed().as_secs_f64(),
+                        self.target_pid
+                    )?;
+                    break;
+                }
+            };
+            let kind = MapSyscall::from_number(machine, call.number);
+            let Some(kind) = kind.filter(|_| call.args[2] & libc::PROT_EXEC as u64 != 0) else {
+                tracer.resume(tid)?;
+                continue;
+            };
+
+            let description = self.describe_syscall(kind, &call);
+            println!("[+] Thread {}: {}", tid, description);
+            writeln!(
+                timeline,
+                "[{:>10.3}] tid {} {}",
+                start.elapsed().as_secs_f64(),
+                tid,
+                description
+            )?;
+            if call.failed() {
+                tracer.resume(tid)?;
+                continue;
+            }
+
+            let address = match kind {
+                MapSyscall::Mmap => call.result as u64,
+                MapSyscall::Mprotect => call.args[0],
+            };
+            let size = (call.args[1] + PAGE_SIZE - 1) & !(PAGE_SIZE - 1);
+            count += 1;
+
+            // 其他线程全部停止后dump，保证看到的是调用返回瞬间的内容
+            tracer.stop_all(Some(tid))?;
+            let lines = self.dump_exec_range(count, address, size);
+            tracer.resume_all()?;
+            tracer.resume(tid)?;
+            for line in lines {
+                writeln!(timeline, "{:12}{}", "", line)?;
+            }
+        }
+
+        println!("[+] Dumped {} executable ranges", count);
+        tracer.detach()
+    }
+
+    /// 轮询/proc/pid/maps直到目标SO被映射，再等待delay让其初始化完成
+    pub fn wait_for_target(
+        &self,
+        interval: Duration,
+        delay: Duration,
+        timeout: Option<Duration>,
+    ) -> Result<()> {
+        println!(
+            "[+] Waiting for {} to be loaded in PID {}",
+            self.target_name, self.target_pid
+        );
+        let started = Instant::now();
+
+        loop {
+            if !Path::new(&format!("/proc/{}", self.target_pid)).exists() {
+                return Err(anyhow!("Process {} exited while waiting", self.target_pid));
+            }
+
+            if let Ok((start, end)) = self.get_target_mapping() {
+                println!(
+                    "[+] {} loaded at {:#x}-{:#x} after {:.1}s",
+                    self.target_name,
+                    start,
+                    end,
+                    started.elapsed().as_secs_f64()
+                );
+                break;
+            }
+
+            if timeout.is_some_and(|timeout| started.elapsed() >= timeout) {
+                return Err(anyhow!("Timed out waiting for {}", self.target_name));
+            }
+            thread::sleep(interval);
+        }
+
+        if !delay.is_zero() {
+            println!("[*] Waiting {}ms for initialization", delay.as_millis());
+            thread::sleep(delay);
+        }
+
+        Ok(())
+    }
+
+    pub fn dump(&self) -> Result<()> {
+        println!(
+            "[+] Starting SO dump process for target: {}",
+            self.target_name
+        );
+        println!("[+] Target PID: {}", self.target_pid);
+        println!("[+] Architecture: {}", self.class);
+
+        let solist_offset = if self.link_map {
+            None
+        } else {
+            let solist_offset = self.find_solist_offset()?;
+            println!("[+] solist offset: {:#x}", solist_offset);
+            Some(solist_offset)
+        };
+
+        self.stop_process()?;
+
+        let result = (|| -> Result<()> {
+            let (target_base, target_end) = self.get_target_mapping()?;
+            let target_size = target_end - target_base;
+            println!(
+                "[+] target base: {:#x}, end: {:#x}, size: {}",
+                target_base, target_end, target_size
+            );
+
+            if self.segment_mode {
+                let _dump_path = self.dump_so_segments(&self.target_name, target_base)?;
+                return Ok(());
+            }
+
+            let solist = match solist_offset {
+                Some(offset) => Some(self.read_solist(offset)?),
+                None => None,
+            };
+            let so_size = self.resolve_so_size(solist.as_ref(), target_base, target_size);
+
+            let _dump_path = self.dump_so(&self.target_name, target_base, so_size)?;
+
+            Ok(())
+        })();
+
+        self.continue_process()?;
+
+        result
+    }
+
+    /// 批量模式：只暂停一次进程，dump所有匹配的SO
+    pub fn dump_all(&self, pattern: Option<&Regex>, app_only: bool) -> Result<()> {
+        println!("[+] Starting batch SO dump process");
+        println!("[+] Target PID: {}", self.target_pid);
+        println!("[+] Architecture: {}", self.class);
+
+        let solist_offset = if self.link_map {
+            None
+        } else {
+            let solist_offset = self.find_solist_offset()?;
+            println!("[+] solist offset: {:#x}", solist_offset);
+            Some(solist_offset)
+        };
+
+        self.stop_process()?;
+
+        let result = (|| -> Result<()> {
+            let solist = match solist_offset {
+                Some(offset) => Some(self.read_solist(offset)?),
+                None => None,
+            };
+
+            let so_files = if self.link_map {
+                list_link_map(self.target_pid, self.reader.as_ref(), self.class)?
+            } else {
+                so_files_in(&self.parse_proc_maps()?)
+            };
+            let targets: Vec<SoFileInfo> = so_files
+                .into_iter()
+                .filter(|so| !app_only || !so.is_system_lib())
+                .filter(|so| pattern.is_none_or(|re| re.is_match(&so.path)))
+                .collect();
+            println!("[+] {} SO files matched", targets.len());
+
+            let mut dumped = 0;
+            for so in &targets {
+                println!(
+                    "[+] target: {}, base: {:#x}, end: {:#x}, size: {}",
+                    so.path, so.start, so.end, so.size
+                );
+                // 不同路径下的同名SO用完整路径命名输出文件
+                let duplicated = targets
+                    .iter()
+                    .any(|other| other.name == so.name && other.path != so.path);
+                let so_name = if duplicated {
+                    so.path.trim_start_matches('/').replace('/', "_")
+                } else {
+                    so.name.clone()
+                };
+                let result = if self.segment_mode {
+                    self.dump_so_segments(&so_name, so.start)
+                } else {
+                    let so_size = self.resolve_so_size(solist.as_ref(), so.start, so.size);
+                    self.dump_so(&so_name, so.start, so_size)
+                };
+                match result {
+                    Ok(_) => dumped += 1,
+                    Err(e) => eprintln!("[!] Failed to dump {}: {}", so.name, e),
+                }
+            }
+
+            println!("[+] Dumped {}/{} SO files", dumped, targets.len());
+            Ok(())
+        })();
+
+        self.continue_process()?;
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testutil::{poll, SpawnedChild, TempDir};
+    use std::process::Command;
+
+    const MARKER: &[u8] = b"TINYDUMP-TRIGGER-OK";
+
+    fn compile(args: &[&OsStr]) {
+        let status = Command::new("cc")
+            .args(args)
+            .status()
+            .expect("cc is required to build the trigger test fixtures");
+        assert!(status.success(), "cc failed: {:?}", args);
+    }
+
+    // 构造函数dlopen的内层SO返回到同一地址，dump应在外层构造函数返回后才触发
+    #[test]
+    fn dump_on_trigger_waits_for_entering_call() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let temp = TempDir::new("trigger");
+        let work = temp.path();
+        let output = work.join("out");
+        fs::create_dir_all(&output).unwrap();
+        let lib = work.join("libtrigger.so");
+        let inner = work.join("libtrigger_inner.so");
+        let host = work.join("trigger_host");
+
+        compile(&[
+            "-shared".as_ref(),
+            "-fPIC".as_ref(),
+            "-o".as_ref(),
+            inner.as_os_str(),
+            fixtures.join("trigger_inner.c").as_os_str(),
+        ]);
+        compile(&[
+            "-shared".as_ref(),
+            "-fPIC".as_ref(),
+            "-o".as_ref(),
+            lib.as_os_str(),
+            fixtures.join("trigger_lib.c").as_os_str(),
+            "-ldl".as_ref(),
+        ]);
+        compile(&[
+            "-o".as_ref(),
+            host.as_os_str(),
+            fixtures.join("trigger_host.c").as_os_str(),
+            "-ldl".as_ref(),
+            "-lpthread".as_ref(),
+        ]);
+
+        let mut child =
+            SpawnedChild::spawn(Command::new(&host).arg(&lib).env("TRIGGER_INNER", &inner));
+        // 宿主创建第二个线程后才sleep，此时ld.so已完成加载，之后才dlopen目标
+        let pid = child.pid();
+        poll(Duration::from_secs(5), || {
+            (fs::read_dir(format!("/proc/{}/task", pid)).unwrap().count() >= 2).then_some(())
+        })
+        .unwrap();
+        let dumper = SoDumper::new(pid, "libtrigger.so".to_string(), output.clone()).unwrap();
+        dumper.dump_on_trigger(BreakTrigger::Init).unwrap();
+        assert!(child.0.wait().unwrap().success());
+
+        let dumps: Vec<Vec<u8>> = fs::read_dir(&output)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension() == Some(OsStr::new("so")))
+            .map(|path| fs::read(path).unwrap())
+            .collect();
+        assert!(!dumps.is_empty());
+        assert!(dumps
+            .iter()
+            .all(|dump| dump.windows(MARKER.len()).any(|w| w == MARKER)));
+    }
 }
@@ -1,84 +1,1072 @@
 use anyhow::{anyhow, Result};
+use byteorder::{LittleEndian, WriteBytesExt};
+use goblin::elf::dynamic::*;
+use goblin::elf::program_header::{PF_X, PT_ARM_EXIDX, PT_DYNAMIC};
+use goblin::elf::section_header::*;
 use std::fs;
-use std::io::Write;
 use std::path::Path;
-use std::process::Command;
 
-// SoFixer64 binary data will be embedded here
+use crate::utils::{
+    decode_relocations, encode_rel_table, min_load_vaddr, parse_dynamic, parse_program_headers,
+    read_cstr, read_sym, read_u32, relative_type, Dyn, ElfClass, ElfHeader, ProgramHeader,
+    RelocFormat, Relocation, DT_ANDROID_REL, DT_ANDROID_RELA, DT_ANDROID_RELASZ, DT_ANDROID_RELR,
+    DT_ANDROID_RELRENT, DT_ANDROID_RELRSZ, DT_ANDROID_RELSZ, DT_RELR, DT_RELRENT, DT_RELRSZ,
+};
+
+const SHT_ARM_EXIDX: u32 = 0x7000_0001;
+const SHT_RELR: u32 = 19;
+const SHT_ANDROID_REL: u32 = 0x6000_0001;
+const SHT_ANDROID_RELA: u32 = 0x6000_0002;
+
+// Rebuilds section headers of a memory dumped SO, based on the dynamic segment.
 // Author: mrack <https://github.com/mrack>
-const SOFIXER64_BINARY: &[u8] = include_bytes!("../../bin/sofixer64");
+const DYNAMIC_PTR_TAGS: &[u64] = &[
+    DT_PLTGOT,
+    DT_HASH,
+    DT_STRTAB,
+    DT_SYMTAB,
+    DT_RELA,
+    DT_INIT,
+    DT_FINI,
+    DT_REL,
+    DT_JMPREL,
+    DT_INIT_ARRAY,
+    DT_FINI_ARRAY,
+    DT_PREINIT_ARRAY,
+    DT_GNU_HASH,
+    DT_VERSYM,
+    DT_VERDEF,
+    DT_VERNEED,
+    DT_RELR,
+    DT_ANDROID_REL,
+    DT_ANDROID_RELA,
+    DT_ANDROID_RELR,
+];
+
+// 展开后由新的DT_REL/DT_RELA取代的动态段项
+const REPLACED_RELOC_TAGS: &[u64] = &[
+    DT_REL,
+    DT_RELSZ,
+    DT_RELENT,
+    DT_RELCOUNT,
+    DT_RELA,
+    DT_RELASZ,
+    DT_RELAENT,
+    DT_RELACOUNT,
+    DT_RELR,
+    DT_RELRSZ,
+    DT_RELRENT,
+    DT_ANDROID_REL,
+    DT_ANDROID_RELSZ,
+    DT_ANDROID_RELA,
+    DT_ANDROID_RELASZ,
+    DT_ANDROID_RELR,
+    DT_ANDROID_RELRSZ,
+    DT_ANDROID_RELRENT,
+];
+
+struct Section {
+    name: &'static str,
+    sh_type: u32,
+    flags: u64,
+    addr: u64,
+    size: u64,
+    link: Option<&'static str>,
+    info: u32,
+    align: u64,
+    entsize: u64,
+}
+
+/// 从动态符号表恢复的已定义符号
+#[derive(Debug, Clone)]
+pub struct DynSymbol {
+    pub name: String,
+    pub value: u64,
+    pub address: u64,
+    pub size: u64,
+    pub sym_type: &'static str,
+    pub bind: &'static str,
+}
 
 pub struct SoFixer {
-    binary_path: String,
+    rebase: Option<u64>,
 }
 
 impl SoFixer {
-    pub fn new() -> Result<Self> {
-        let binary_path = "./SoFixer".to_string();
-        Ok(Self { binary_path })
+    pub fn new() -> Self {
+        Self { rebase: None }
     }
 
-    pub fn extract(&self) -> Result<()> {
-        let output_path = Path::new(&self.binary_path);
+    /// 将RELATIVE重定位还原为相对给定基址的值，0即为文件中的原始值
+    pub fn set_rebase(&mut self, rebase: Option<u64>) {
+        self.rebase = rebase;
+    }
+
+    pub fn fix_so(&self, base: u64, so_path: &str, output_path: &str) -> Result<()> {
+        let dump = fs::read(so_path)?;
+        let fixed = self.rebuild(&dump, base)?;
+        fs::write(Path::new(output_path), fixed)?;
 
-        if let Some(parent) = output_path.parent() {
-            fs::create_dir_all(parent)?;
+        println!("[+] SO fixed successfully: {}", output_path);
+        Ok(())
+    }
+
+    /// 根据内存dump和加载基址重建ELF文件
+    pub fn rebuild(&self, dump: &[u8], base: u64) -> Result<Vec<u8>> {
+        let header = ElfHeader::parse(dump)?;
+        let class = header.class;
+        let mut phdrs = parse_program_headers(dump, &header)?;
+        let min_vaddr = min_load_vaddr(&phdrs);
+        let load_bias = base.wrapping_sub(min_vaddr);
+
+        let mut out = dump.to_vec();
+
+        let (dynamic_phdr, dynamic_off, dynamic) =
+            Self::read_dynamic(dump, &phdrs, class, min_vaddr, base)?;
+        for (i, entry) in dynamic.iter().enumerate() {
+            if DYNAMIC_PTR_TAGS.contains(&entry.d_tag) {
+                let val_off = dynamic_off + i * class.dyn_size() + class.ptr_size();
+                class.write_word(&mut out, val_off, entry.d_val);
+            }
         }
+        let dyn_val = |tag: u64| dynamic.iter().find(|d| d.d_tag == tag).map(|d| d.d_val);
 
-        let mut file = fs::File::create(output_path)?;
-        file.write_all(SOFIXER64_BINARY)?;
+        for phdr in phdrs.iter_mut() {
+            if phdr.p_memsz == 0 {
+                continue;
+            }
+            let offset = phdr.p_vaddr.saturating_sub(min_vaddr);
+            phdr.p_offset = offset;
+            phdr.p_paddr = phdr.p_vaddr;
+            phdr.p_filesz = phdr.p_memsz.min((dump.len() as u64).saturating_sub(offset));
+        }
+        for (i, phdr) in phdrs.iter().enumerate() {
+            phdr.write(
+                &mut out,
+                header.e_phoff as usize + i * class.phdr_size(),
+                class,
+            );
+        }
 
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let mut perms = fs::metadata(output_path)?.permissions();
-            perms.set_mode(0o755);
-            fs::set_permissions(output_path, perms)?;
+        if let Some(new_base) = self.rebase {
+            let count =
+                Self::rebase_relative(&mut out, &header, min_vaddr, load_bias, new_base, &dynamic)?;
+            println!(
+                "[+] Rebased {} relative relocations to {:#x}",
+                count, new_base
+            );
         }
 
-        println!("[+] SoFixer extracted to: {}", self.binary_path);
-        Ok(())
+        let to_off = |vaddr: u64| vaddr.checked_sub(min_vaddr).map(|v| v as usize);
+        let ptr = class.ptr_size() as u64;
+        let mut sections = Vec::new();
+
+        let nsyms = Self::dynsym_count(dump, class, min_vaddr, &dynamic);
+
+        if let (Some(symtab), Some(nsyms)) = (dyn_val(DT_SYMTAB), nsyms) {
+            sections.push(Section {
+                name: ".dynsym",
+                sh_type: SHT_DYNSYM,
+                flags: SHF_ALLOC as u64,
+                addr: symtab,
+                size: nsyms * class.sym_size() as u64,
+                link: Some(".dynstr"),
+                info: 1,
+                align: ptr,
+                entsize: class.sym_size() as u64,
+            });
+        }
+
+        if let (Some(strtab), Some(strsz)) = (dyn_val(DT_STRTAB), dyn_val(DT_STRSZ)) {
+            sections.push(Section {
+                name: ".dynstr",
+                sh_type: SHT_STRTAB,
+                flags: SHF_ALLOC as u64,
+                addr: strtab,
+                size: strsz,
+                link: None,
+                info: 0,
+                align: 1,
+                entsize: 0,
+            });
+        }
+
+        if let Some(hash) = dyn_val(DT_HASH) {
+            let nbucket = to_off(hash).and_then(|o| read_u32(dump, o)).unwrap_or(0) as u64;
+            let nchain = to_off(hash)
+                .and_then(|o| read_u32(dump, o + 4))
+                .unwrap_or(0) as u64;
+            sections.push(Section {
+                name: ".hash",
+                sh_type: SHT_HASH,
+                flags: SHF_ALLOC as u64,
+                addr: hash,
+                size: (2 + nbucket + nchain) * 4,
+                link: Some(".dynsym"),
+                info: 0,
+                align: 4,
+                entsize: 4,
+            });
+        }
+
+        if let Some(gnu_hash) = dyn_val(DT_GNU_HASH) {
+            if let Some(size) = to_off(gnu_hash)
+                .and_then(|o| Self::gnu_hash_size(dump, o, class, nsyms.unwrap_or(0)))
+            {
+                sections.push(Section {
+                    name: ".gnu.hash",
+                    sh_type: SHT_GNU_HASH,
+                    flags: SHF_ALLOC as u64,
+                    addr: gnu_hash,
+                    size,
+                    link: Some(".dynsym"),
+                    info: 0,
+                    align: ptr,
+                    entsize: 0,
+                });
+            }
+        }
+
+        if let (Some(rela), Some(relasz)) = (dyn_val(DT_RELA), dyn_val(DT_RELASZ)) {
+            sections.push(Section {
+                name: ".rela.dyn",
+                sh_type: SHT_RELA,
+                flags: SHF_ALLOC as u64,
+                addr: rela,
+                size: relasz,
+                link: Some(".dynsym"),
+                info: 0,
+                align: ptr,
+                entsize: ptr * 3,
+            });
+        }
+
+        if let (Some(rel), Some(relsz)) = (dyn_val(DT_REL), dyn_val(DT_RELSZ)) {
+            sections.push(Section {
+                name: ".rel.dyn",
+                sh_type: SHT_REL,
+                flags: SHF_ALLOC as u64,
+                addr: rel,
+                size: relsz,
+                link: Some(".dynsym"),
+                info: 0,
+                align: ptr,
+                entsize: ptr * 2,
+            });
+        }
+
+        if let (Some(jmprel), Some(pltrelsz)) = (dyn_val(DT_JMPREL), dyn_val(DT_PLTRELSZ)) {
+            let is_rela = dyn_val(DT_PLTREL) == Some(DT_RELA);
+            sections.push(Section {
+                name: if is_rela { ".rela.plt" } else { ".rel.plt" },
+                sh_type: if is_rela { SHT_RELA } else { SHT_REL },
+                flags: SHF_ALLOC as u64,
+                addr: jmprel,
+                size: pltrelsz,
+                link: Some(".dynsym"),
+                info: 0,
+                align: ptr,
+                entsize: if is_rela { ptr * 3 } else { ptr * 2 },
+            });
+        }
+
+        for (addr_tag, size_tag, name, sh_type, entsize) in [
+            (
+                DT_ANDROID_RELA,
+                DT_ANDROID_RELASZ,
+                ".rela.android",
+                SHT_ANDROID_RELA,
+                1,
+            ),
+            (
+                DT_ANDROID_REL,
+                DT_ANDROID_RELSZ,
+                ".rel.android",
+                SHT_ANDROID_REL,
+                1,
+            ),
+            (DT_RELR, DT_RELRSZ, ".relr.dyn", SHT_RELR, ptr),
+            (
+                DT_ANDROID_RELR,
+                DT_ANDROID_RELRSZ,
+                ".relr.dyn",
+                SHT_RELR,
+                ptr,
+            ),
+        ] {
+            if let (Some(addr), Some(size)) = (dyn_val(addr_tag), dyn_val(size_tag)) {
+                sections.push(Section {
+                    name,
+                    sh_type,
+                    flags: SHF_ALLOC as u64,
+                    addr,
+                    size,
+                    link: (sh_type != SHT_RELR).then_some(".dynsym"),
+                    info: 0,
+                    align: ptr,
+                    entsize,
+                });
+            }
+        }
+
+        if let (Some(init), Some(size)) = (dyn_val(DT_INIT_ARRAY), dyn_val(DT_INIT_ARRAYSZ)) {
+            sections.push(Section {
+                name: ".init_array",
+                sh_type: SHT_INIT_ARRAY,
+                flags: (SHF_WRITE | SHF_ALLOC) as u64,
+                addr: init,
+                size,
+                link: None,
+                info: 0,
+                align: ptr,
+                entsize: ptr,
+            });
+        }
+
+        if let (Some(fini), Some(size)) = (dyn_val(DT_FINI_ARRAY), dyn_val(DT_FINI_ARRAYSZ)) {
+            sections.push(Section {
+                name: ".fini_array",
+                sh_type: SHT_FINI_ARRAY,
+                flags: (SHF_WRITE | SHF_ALLOC) as u64,
+                addr: fini,
+                size,
+                link: None,
+                info: 0,
+                align: ptr,
+                entsize: ptr,
+            });
+        }
+
+        sections.push(Section {
+            name: ".dynamic",
+            sh_type: SHT_DYNAMIC,
+            flags: (SHF_WRITE | SHF_ALLOC) as u64,
+            addr: dynamic_phdr.p_vaddr,
+            size: dynamic_phdr.p_memsz,
+            link: Some(".dynstr"),
+            info: 0,
+            align: ptr,
+            entsize: class.dyn_size() as u64,
+        });
+
+        if let Some(text) = Self::guess_text(&phdrs, &header, min_vaddr, &sections) {
+            sections.push(text);
+        }
+
+        if let Some(exidx) = phdrs.iter().find(|p| p.p_type == PT_ARM_EXIDX) {
+            sections.push(Section {
+                name: ".ARM.exidx",
+                sh_type: SHT_ARM_EXIDX,
+                flags: (SHF_ALLOC | SHF_LINK_ORDER) as u64,
+                addr: exidx.p_vaddr,
+                size: exidx.p_memsz,
+                link: Some(".text"),
+                info: 0,
+                align: 4,
+                entsize: 8,
+            });
+        }
+
+        // 丢弃超出dump范围的节区
+        sections.retain(|s| to_off(s.addr).is_some_and(|o| o < dump.len()));
+        // DT_*SZ可能被篡改为任意值，截断到dump末尾
+        for section in sections.iter_mut() {
+            let off = section.addr - min_vaddr;
+            section.size = section.size.min(dump.len() as u64 - off);
+        }
+        sections.sort_by_key(|s| s.addr);
+
+        // APS2和RELR展开为标准重定位表，追加在dump之后，地址与文件偏移保持一致
+        let relocation_bias = self.rebase.unwrap_or(load_bias);
+        let (expanded, is_rela) =
+            Self::expand_packed_relocs(dump, &out, &header, min_vaddr, relocation_bias, &dynamic)?;
+        if !expanded.is_empty() {
+            let table = encode_rel_table(&expanded, class, is_rela);
+            out.resize(align_up(out.len() as u64, ptr) as usize, 0);
+            let table_addr = min_vaddr + out.len() as u64;
+            sections.push(Section {
+                name: if is_rela {
+                    ".rela.expanded"
+                } else {
+                    ".rel.expanded"
+                },
+                sh_type: if is_rela { SHT_RELA } else { SHT_REL },
+                flags: SHF_ALLOC as u64,
+                addr: table_addr,
+                size: table.len() as u64,
+                link: Some(".dynsym"),
+                info: 0,
+                align: ptr,
+                entsize: if is_rela { ptr * 3 } else { ptr * 2 },
+            });
+            out.extend_from_slice(&table);
+
+            // 最后一个PT_LOAD延伸到表尾，按程序头映射地址的加载器和工具才能读到新表
+            let table_end = min_vaddr + out.len() as u64;
+            if let Some((i, last)) = phdrs
+                .iter_mut()
+                .enumerate()
+                .filter(|(_, p)| p.is_load())
+                .max_by_key(|(_, p)| p.p_vaddr)
+            {
+                last.p_memsz = table_end - last.p_vaddr;
+                last.p_filesz = last.p_memsz;
+                last.write(
+                    &mut out,
+                    header.e_phoff as usize + i * class.phdr_size(),
+                    class,
+                );
+            }
+
+            let dynamic_end = dynamic_off.saturating_add(dynamic_phdr.p_memsz as usize);
+            Self::retarget_dynamic(
+                &mut out[dynamic_off..dynamic_end.min(dump.len())],
+                &dynamic,
+                class,
+                is_rela,
+                table_addr,
+                table.len() as u64,
+            )?;
+            println!(
+                "[+] Expanded {} relocations to a standard {} table",
+                expanded.len(),
+                if is_rela { "RELA" } else { "REL" }
+            );
+        }
+
+        let mut shstrtab = vec![0u8];
+        let mut name_offsets = Vec::with_capacity(sections.len() + 1);
+        for section in &sections {
+            name_offsets.push(shstrtab.len() as u32);
+            shstrtab.extend_from_slice(section.name.as_bytes());
+            shstrtab.push(0);
+        }
+        let shstrtab_name = shstrtab.len() as u32;
+        shstrtab.extend_from_slice(b".shstrtab\0");
+
+        out.resize(align_up(out.len() as u64, ptr) as usize, 0);
+        let shstrtab_off = out.len() as u64;
+        out.extend_from_slice(&shstrtab);
+        out.resize(align_up(out.len() as u64, ptr) as usize, 0);
+        let shoff = out.len() as u64;
+
+        let index_of = |name: &str| {
+            sections
+                .iter()
+                .position(|s| s.name == name)
+                .map(|i| i as u32 + 1)
+                .unwrap_or(0)
+        };
+
+        write_shdr(&mut out, class, &[0; 10]);
+        for (section, name_off) in sections.iter().zip(&name_offsets) {
+            write_shdr(
+                &mut out,
+                class,
+                &[
+                    *name_off as u64,
+                    section.sh_type as u64,
+                    section.flags,
+                    section.addr,
+                    section.addr - min_vaddr,
+                    section.size,
+                    section.link.map(index_of).unwrap_or(0) as u64,
+                    section.info as u64,
+                    section.align,
+                    section.entsize,
+                ],
+            );
+        }
+        write_shdr(
+            &mut out,
+            class,
+            &[
+                shstrtab_name as u64,
+                SHT_STRTAB as u64,
+                0,
+                0,
+                shstrtab_off,
+                shstrtab.len() as u64,
+                0,
+                0,
+                1,
+                0,
+            ],
+        );
+
+        let shnum = sections.len() as u16 + 2;
+        header
+            .patch_section_info(&mut out, shoff, shnum, shnum - 1)
+            .ok_or_else(|| anyhow!("Failed to patch ELF header"))?;
+
+        println!(
+            "[+] Rebuilt {} sections ({}), load bias: {:#x}",
+            sections.len(),
+            sections
+                .iter()
+                .map(|s| s.name)
+                .collect::<Vec<_>>()
+                .join(" "),
+            load_bias
+        );
+
+        Ok(out)
     }
 
-    pub fn fix_so(&self, base: u64, so_path: &str, output_path: &str) -> Result<()> {
-        if !Path::new(&self.binary_path).exists() {
-            self.extract()?;
+    /// 从内存dump的动态段中读取导出符号，value为虚拟地址，address为运行时地址
+    pub fn extract_symbols(&self, dump: &[u8], base: u64) -> Result<Vec<DynSymbol>> {
+        let header = ElfHeader::parse(dump)?;
+        let class = header.class;
+        let phdrs = parse_program_headers(dump, &header)?;
+        let min_vaddr = min_load_vaddr(&phdrs);
+        let load_bias = base.wrapping_sub(min_vaddr);
+
+        let (_, _, dynamic) = Self::read_dynamic(dump, &phdrs, class, min_vaddr, base)?;
+        let dyn_val = |tag: u64| dynamic.iter().find(|d| d.d_tag == tag).map(|d| d.d_val);
+        let to_off = |vaddr: u64| vaddr.checked_sub(min_vaddr).map(|v| v as usize);
+
+        let symtab = dyn_val(DT_SYMTAB)
+            .and_then(to_off)
+            .ok_or_else(|| anyhow!("No DT_SYMTAB in dump"))?;
+        let strtab = dyn_val(DT_STRTAB)
+            .and_then(to_off)
+            .ok_or_else(|| anyhow!("No DT_STRTAB in dump"))?;
+        let nsyms = Self::dynsym_count(dump, class, min_vaddr, &dynamic)
+            .ok_or_else(|| anyhow!("Could not determine dynamic symbol count"))?;
+
+        let mut symbols = Vec::new();
+        for index in 1..nsyms as usize {
+            let Some(sym) = read_sym(dump, symtab + index * class.sym_size(), class) else {
+                break;
+            };
+            if sym.st_shndx == SHN_UNDEF as u16 {
+                continue;
+            }
+            let Some(name) = read_cstr(dump, strtab + sym.st_name as usize) else {
+                continue;
+            };
+
+            symbols.push(DynSymbol {
+                name,
+                value: sym.st_value,
+                address: load_bias.wrapping_add(sym.st_value),
+                size: sym.st_size,
+                sym_type: sym_type_name(sym.st_info & 0xf),
+                bind: sym_bind_name(sym.st_info >> 4),
+            });
         }
 
-        let output = Command::new(&self.binary_path)
-            .arg("-m")
-            .arg(format!("{:#x}", base))
-            .arg("-s")
-            .arg(so_path)
-            .arg("-o")
-            .arg(output_path)
-            .output()?;
+        Ok(symbols)
+    }
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(anyhow!("SoFixer failed: {}", stderr));
+    // 原有的DT_REL/DT_RELA一并写入新表，动态段指向新表后不会丢失；DT_JMPREL保持不变
+    // 64位以及32位原表带addend时输出RELA，RELR等隐式addend的RELATIVE项从当前镜像中读出目标值减去基址作为addend
+    fn expand_packed_relocs(
+        dump: &[u8],
+        image: &[u8],
+        header: &ElfHeader,
+        min_vaddr: u64,
+        relocation_bias: u64,
+        dynamic: &[Dyn],
+    ) -> Result<(Vec<Relocation>, bool)> {
+        let class = header.class;
+        let dynamic: Vec<Dyn> = dynamic
+            .iter()
+            .filter(|d| d.d_tag != DT_JMPREL)
+            .cloned()
+            .collect();
+        let relocs = decode_relocations(dump, class, header.e_machine, min_vaddr, &dynamic)?;
+        let packed = relocs.iter().any(|r| {
+            matches!(
+                r.format,
+                RelocFormat::AndroidRel | RelocFormat::AndroidRela | RelocFormat::Relr
+            )
+        });
+        if !packed {
+            return Ok((Vec::new(), false));
         }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        if !stdout.is_empty() {
-            println!("[+] SoFixer output: {}", stdout);
+        let is_rela = class == ElfClass::Elf64
+            || relocs
+                .iter()
+                .any(|r| matches!(r.format, RelocFormat::Rela | RelocFormat::AndroidRela));
+        let relative = relative_type(header.e_machine);
+        let relocs = relocs
+            .into_iter()
+            .map(|mut r| {
+                let implicit = !matches!(r.format, RelocFormat::Rela | RelocFormat::AndroidRela);
+                if is_rela && implicit && Some(r.r_type) == relative {
+                    let value = r
+                        .r_offset
+                        .checked_sub(min_vaddr)
+                        .and_then(|o| class.read_word(image, o as usize))
+                        .unwrap_or(0);
+                    r.r_addend = value.wrapping_sub(relocation_bias) as i64;
+                }
+                r
+            })
+            .collect();
+
+        Ok((relocs, is_rela))
+    }
+
+    // 去掉原有的重定位项，追加指向展开表的DT_RELA/DT_REL，空间不足时省略*ENT
+    fn retarget_dynamic(
+        area: &mut [u8],
+        dynamic: &[Dyn],
+        class: ElfClass,
+        is_rela: bool,
+        table_addr: u64,
+        table_size: u64,
+    ) -> Result<()> {
+        let ptr = class.ptr_size() as u64;
+        let capacity = area.len() / class.dyn_size();
+        let (addr_tag, size_tag, ent_tag, entsize) = if is_rela {
+            (DT_RELA, DT_RELASZ, DT_RELAENT, ptr * 3)
+        } else {
+            (DT_REL, DT_RELSZ, DT_RELENT, ptr * 2)
+        };
+
+        let mut entries: Vec<(u64, u64)> = dynamic
+            .iter()
+            .filter(|d| !REPLACED_RELOC_TAGS.contains(&d.d_tag))
+            .map(|d| (d.d_tag, d.d_val))
+            .collect();
+        entries.push((addr_tag, table_addr));
+        entries.push((size_tag, table_size));
+        // 末尾至少保留一项DT_NULL
+        if entries.len() + 2 <= capacity {
+            entries.push((ent_tag, entsize));
+        }
+        if entries.len() + 1 > capacity {
+            return Err(anyhow!("No room in .dynamic for the expanded relocations"));
         }
 
-        println!("[+] SO fixed successfully: {}", output_path);
+        for i in 0..capacity {
+            let (tag, val) = entries.get(i).copied().unwrap_or((DT_NULL, 0));
+            let offset = i * class.dyn_size();
+            class.write_word(area, offset, tag);
+            class.write_word(area, offset + ptr as usize, val);
+        }
         Ok(())
     }
 
-    pub fn cleanup(&self) -> Result<()> {
-        if Path::new(&self.binary_path).exists() {
-            fs::remove_file(&self.binary_path)?;
-            println!("[+] SoFixer binary cleaned up");
+    /// 解码dump中的所有重定位，附带符号名
+    pub fn decode_relocs(
+        &self,
+        dump: &[u8],
+        base: u64,
+    ) -> Result<Vec<(Relocation, Option<String>)>> {
+        let header = ElfHeader::parse(dump)?;
+        let class = header.class;
+        let phdrs = parse_program_headers(dump, &header)?;
+        let min_vaddr = min_load_vaddr(&phdrs);
+
+        let (_, _, dynamic) = Self::read_dynamic(dump, &phdrs, class, min_vaddr, base)?;
+        let dyn_val = |tag: u64| dynamic.iter().find(|d| d.d_tag == tag).map(|d| d.d_val);
+        let to_off = |vaddr: u64| vaddr.checked_sub(min_vaddr).map(|v| v as usize);
+        let symtab = dyn_val(DT_SYMTAB).and_then(to_off);
+        let strtab = dyn_val(DT_STRTAB).and_then(to_off);
+
+        let relocs = decode_relocations(dump, class, header.e_machine, min_vaddr, &dynamic)?;
+        Ok(relocs
+            .into_iter()
+            .map(|r| {
+                let name = (r.r_sym != 0)
+                    .then(|| {
+                        let sym =
+                            read_sym(dump, symtab? + r.r_sym as usize * class.sym_size(), class)?;
+                        read_cstr(dump, strtab? + sym.st_name as usize)
+                    })
+                    .flatten();
+                (r, name)
+            })
+            .collect())
+    }
+
+    // 从R_*_RELATIVE目标中减去加载偏移，得到与磁盘文件一致的指针
+    fn rebase_relative(
+        out: &mut [u8],
+        header: &ElfHeader,
+        min_vaddr: u64,
+        load_bias: u64,
+        new_base: u64,
+        dynamic: &[Dyn],
+    ) -> Result<usize> {
+        let class = header.class;
+        let relative = relative_type(header.e_machine)
+            .ok_or_else(|| anyhow!("Unsupported machine {} for rebasing", header.e_machine))?;
+        let relocs = decode_relocations(out, class, header.e_machine, min_vaddr, dynamic)?;
+
+        let mut count = 0;
+        for reloc in relocs.iter().filter(|r| r.r_type == relative) {
+            let Some(offset) = reloc.r_offset.checked_sub(min_vaddr).map(|o| o as usize) else {
+                continue;
+            };
+            if let Some(value) = class.read_word(out, offset) {
+                let rebased = value.wrapping_sub(load_bias).wrapping_add(new_base);
+                if class.write_word(out, offset, rebased).is_some() {
+                    count += 1;
+                }
+            }
         }
-        Ok(())
+
+        Ok(count)
+    }
+
+    // 解析动态段，glibc会把其中的地址重定位为运行时地址，这里还原为虚拟地址
+    fn read_dynamic(
+        dump: &[u8],
+        phdrs: &[ProgramHeader],
+        class: ElfClass,
+        min_vaddr: u64,
+        base: u64,
+    ) -> Result<(ProgramHeader, usize, Vec<Dyn>)> {
+        let load_bias = base.wrapping_sub(min_vaddr);
+        let dynamic_phdr = phdrs
+            .iter()
+            .find(|p| p.p_type == PT_DYNAMIC)
+            .cloned()
+            .ok_or_else(|| anyhow!("No PT_DYNAMIC segment in dump"))?;
+        let dynamic_off = dynamic_phdr
+            .p_vaddr
+            .checked_sub(min_vaddr)
+            .filter(|off| *off < dump.len() as u64)
+            .ok_or_else(|| anyhow!("PT_DYNAMIC is outside of the dump"))?
+            as usize;
+
+        let mut dynamic = parse_dynamic(&dump[dynamic_off..], class);
+        for entry in dynamic.iter_mut() {
+            if DYNAMIC_PTR_TAGS.contains(&entry.d_tag) && base != 0 && entry.d_val >= base {
+                entry.d_val -= load_bias;
+            }
+        }
+
+        Ok((dynamic_phdr, dynamic_off, dynamic))
+    }
+
+    // 优先使用哈希表计算符号数，没有时按symtab到strtab的距离估算
+    fn dynsym_count(dump: &[u8], class: ElfClass, min_vaddr: u64, dynamic: &[Dyn]) -> Option<u64> {
+        let dyn_val = |tag: u64| dynamic.iter().find(|d| d.d_tag == tag).map(|d| d.d_val);
+        let to_off = |vaddr: u64| vaddr.checked_sub(min_vaddr).map(|v| v as usize);
+
+        Self::symbol_count(dump, class, &to_off, dyn_val(DT_HASH), dyn_val(DT_GNU_HASH)).or_else(
+            || {
+                let symtab = dyn_val(DT_SYMTAB)?;
+                let strtab = dyn_val(DT_STRTAB)?;
+                (strtab > symtab).then(|| (strtab - symtab) / class.sym_size() as u64)
+            },
+        )
+    }
+
+    fn symbol_count(
+        dump: &[u8],
+        class: ElfClass,
+        to_off: &dyn Fn(u64) -> Option<usize>,
+        hash: Option<u64>,
+        gnu_hash: Option<u64>,
+    ) -> Option<u64> {
+        if let Some(nchain) = hash.and_then(to_off).and_then(|o| read_u32(dump, o + 4)) {
+            return Some(nchain as u64);
+        }
+
+        let off = gnu_hash.and_then(to_off)?;
+        let nbuckets = read_u32(dump, off)? as usize;
+        let symoffset = read_u32(dump, off + 4)?;
+        let bloom_size = read_u32(dump, off + 8)? as usize;
+        let buckets_off = off + 16 + bloom_size * class.ptr_size();
+        let chains_off = buckets_off + nbuckets * 4;
+
+        let max_bucket = (0..nbuckets)
+            .filter_map(|i| read_u32(dump, buckets_off + i * 4))
+            .max()?;
+        if max_bucket < symoffset {
+            return Some(symoffset as u64);
+        }
+
+        let mut index = max_bucket;
+        loop {
+            let hash = read_u32(dump, chains_off + (index - symoffset) as usize * 4)?;
+            index += 1;
+            if hash & 1 != 0 {
+                break;
+            }
+        }
+        Some(index as u64)
+    }
+
+    fn gnu_hash_size(dump: &[u8], off: usize, class: ElfClass, nsyms: u64) -> Option<u64> {
+        let nbuckets = read_u32(dump, off)? as u64;
+        let symoffset = read_u32(dump, off + 4)? as u64;
+        let bloom_size = read_u32(dump, off + 8)? as u64;
+        Some(
+            16 + bloom_size * class.ptr_size() as u64
+                + nbuckets * 4
+                + nsyms.saturating_sub(symoffset) * 4,
+        )
+    }
+
+    fn guess_text(
+        phdrs: &[ProgramHeader],
+        header: &ElfHeader,
+        min_vaddr: u64,
+        sections: &[Section],
+    ) -> Option<Section> {
+        let exec = phdrs
+            .iter()
+            .find(|p| p.is_load() && p.p_flags & PF_X != 0)?;
+        let seg_start = exec.p_vaddr;
+        let seg_end = exec.p_vaddr.saturating_add(exec.p_memsz);
+
+        // 旧式布局中可执行段从ELF头开始，跳过位于同一段内的头部和已知节区
+        // 节区大小此时还未截断，DT_*SZ和程序头都可能被篡改，计算时不能溢出
+        let headers_end = min_vaddr
+            .saturating_add(header.e_phoff)
+            .saturating_add(header.e_phnum as u64 * header.class.phdr_size() as u64);
+        let start = sections
+            .iter()
+            .filter(|s| s.addr >= seg_start && s.addr < seg_end)
+            .map(|s| s.addr.saturating_add(s.size))
+            .chain((seg_start <= min_vaddr).then_some(headers_end))
+            .fold(seg_start, u64::max);
+        let start = start.checked_next_multiple_of(4)?;
+
+        // .ARM.exidx通常紧跟在.text之后
+        let end = phdrs
+            .iter()
+            .filter(|p| p.p_type == PT_ARM_EXIDX && p.p_vaddr > start)
+            .map(|p| p.p_vaddr)
+            .fold(seg_end, u64::min);
+
+        (start < end).then(|| Section {
+            name: ".text",
+            sh_type: SHT_PROGBITS,
+            flags: (SHF_ALLOC | SHF_EXECINSTR) as u64,
+            addr: start,
+            size: end - start,
+            link: None,
+            info: 0,
+            align: 4,
+            entsize: 0,
+        })
+    }
+}
+
+impl Default for SoFixer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn sym_type_name(st_type: u8) -> &'static str {
+    match st_type {
+        0 => "NOTYPE",
+        1 => "OBJECT",
+        2 => "FUNC",
+        3 => "SECTION",
+        4 => "FILE",
+        5 => "COMMON",
+        6 => "TLS",
+        10 => "IFUNC",
+        _ => "UNKNOWN",
     }
 }
 
-impl Drop for SoFixer {
-    fn drop(&mut self) {
-        let _ = self.cleanup();
+fn sym_bind_name(st_bind: u8) -> &'static str {
+    match st_bind {
+        0 => "LOCAL",
+        1 => "GLOBAL",
+        2 => "WEAK",
+        10 => "UNIQUE",
+        _ => "UNKNOWN",
+    }
+}
+
+fn align_up(value: u64, align: u64) -> u64 {
+    (value + align - 1) & !(align - 1)
+}
+
+// name, type, flags, addr, offset, size, link, info, addralign, entsize
+fn write_shdr(out: &mut Vec<u8>, class: ElfClass, fields: &[u64; 10]) {
+    for (i, value) in fields.iter().enumerate() {
+        let is_u32 = matches!(i, 0 | 1 | 6 | 7);
+        let _ = if is_u32 || class == ElfClass::Elf32 {
+            out.write_u32::<LittleEndian>(*value as u32)
+        } else {
+            out.write_u64::<LittleEndian>(*value)
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use goblin::container::{Container, Ctx, Endian};
+    use goblin::elf::Elf;
+
+    const FIXTURE: &[u8] = include_bytes!("../../tests/fixtures/libfixture_x86_64_dump.bin");
+    const FIXTURE_BASE: u64 = 0x7f64_1345_c000;
+    const FIXTURE_DYNAMIC: usize = 0x3ef0;
+
+    // 修改dump中动态段的某一项
+    fn patch_dynamic(dump: &mut [u8], tag: u64, new_tag: u64, new_val: Option<u64>) {
+        let class = ElfClass::Elf64;
+        let entry = (0..)
+            .map(|i| FIXTURE_DYNAMIC + i * class.dyn_size())
+            .find(|&off| class.read_word(dump, off) == Some(tag))
+            .unwrap();
+        class.write_word(dump, entry, new_tag).unwrap();
+        if let Some(val) = new_val {
+            class.write_word(dump, entry + 8, val).unwrap();
+        }
+    }
+
+    // 只解析节区头，不解析动态段，篡改过的DT_STRSZ也能读取
+    fn sections(image: &[u8]) -> Vec<(String, u64, u64)> {
+        let header = Elf::parse_header(image).unwrap();
+        let ctx = Ctx::new(Container::Big, Endian::Little);
+        let shdrs =
+            SectionHeader::parse(image, header.e_shoff as usize, header.e_shnum as usize, ctx)
+                .unwrap();
+        let shstrtab = shdrs[header.e_shstrndx as usize].sh_offset as usize;
+
+        shdrs
+            .iter()
+            .skip(1)
+            .map(|sh| {
+                let name = read_cstr(image, shstrtab + sh.sh_name).unwrap();
+                (name, sh.sh_addr, sh.sh_size)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rebuild_recovers_sections() {
+        let fixed = SoFixer::new().rebuild(FIXTURE, FIXTURE_BASE).unwrap();
+        let expected = [
+            (".hash", 0x260, 0x2c),
+            (".gnu.hash", 0x290, 0x38),
+            (".dynsym", 0x2c8, 0x90),
+            (".dynstr", 0x358, 0x47),
+            (".rela.dyn", 0x3a0, 0x30),
+            (".text", 0x1000, 0x12),
+            (".dynamic", 0x3ef0, 0xf0),
+            (".shstrtab", 0, 0x44),
+        ];
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|(name, addr, size)| (name.to_string(), *addr, *size))
+            .collect();
+
+        assert_eq!(sections(&fixed), expected);
+    }
+
+    #[test]
+    fn rebuild_restores_dynamic_addresses() {
+        let fixed = SoFixer::new().rebuild(FIXTURE, FIXTURE_BASE).unwrap();
+        let elf = Elf::parse(&fixed).unwrap();
+        let dynamic = elf.dynamic.unwrap();
+
+        assert_eq!(dynamic.info.symtab, 0x2c8);
+        assert_eq!(dynamic.info.strtab, 0x358);
+        assert_eq!(dynamic.info.rela, 0x3a0);
+        assert_eq!(elf.dynsyms.len(), 6);
+    }
+
+    #[test]
+    fn dynsym_count_from_gnu_hash() {
+        let mut dump = FIXTURE.to_vec();
+        patch_dynamic(&mut dump, DT_HASH, DT_DEBUG, None);
+        let fixed = SoFixer::new().rebuild(&dump, FIXTURE_BASE).unwrap();
+        let dynsym = sections(&fixed)
+            .into_iter()
+            .find(|(name, _, _)| name == ".dynsym")
+            .unwrap();
+
+        assert_eq!(dynsym.2 / ElfClass::Elf64.sym_size() as u64, 6);
+    }
+
+    #[test]
+    fn rebuild_clamps_tampered_sizes() {
+        let mut dump = FIXTURE.to_vec();
+        patch_dynamic(&mut dump, DT_STRSZ, DT_STRSZ, Some(u64::MAX));
+        let fixed = SoFixer::new().rebuild(&dump, FIXTURE_BASE).unwrap();
+        let dynstr = sections(&fixed)
+            .into_iter()
+            .find(|(name, _, _)| name == ".dynstr")
+            .unwrap();
+
+        assert_eq!(dynstr.2, FIXTURE.len() as u64 - 0x358);
+    }
+
+    #[test]
+    fn rebuild_tampered_sizes_in_exec_segment() {
+        // 旧式单一R+X段布局：第一个PT_LOAD可执行，.dynstr位于其中
+        let mut dump = FIXTURE.to_vec();
+        let flags = 0x40 + 4;
+        dump[flags] |= PF_X as u8;
+        patch_dynamic(&mut dump, DT_STRSZ, DT_STRSZ, Some(u64::MAX));
+        let fixed = SoFixer::new().rebuild(&dump, FIXTURE_BASE).unwrap();
+        let names: Vec<_> = sections(&fixed)
+            .into_iter()
+            .map(|(name, _, _)| name)
+            .collect();
+
+        assert!(names.iter().any(|name| name == ".dynstr"));
+    }
+
+    #[test]
+    fn rebuild_points_dynamic_at_expanded_relocs() {
+        // 0x3ff8处为已重定位的指针，由追加的一项RELR描述
+        let class = ElfClass::Elf64;
+        let mut dump = FIXTURE.to_vec();
+        class
+            .write_word(&mut dump, 0x3ff8, FIXTURE_BASE + 0x1000)
+            .unwrap();
+        class.write_word(&mut dump, 0x3d8, 0x3ff8).unwrap();
+        patch_dynamic(&mut dump, DT_NULL, DT_RELR, Some(FIXTURE_BASE + 0x3d8));
+        patch_dynamic(&mut dump, DT_NULL, DT_RELRSZ, Some(8));
+
+        let fixed = SoFixer::new().rebuild(&dump, FIXTURE_BASE).unwrap();
+        let elf = Elf::parse(&fixed).unwrap();
+        let dynamic = elf.dynamic.as_ref().unwrap();
+        let table = sections(&fixed)
+            .into_iter()
+            .find(|(name, _, _)| name == ".rela.expanded")
+            .unwrap();
+
+        assert!(dynamic.dyns.iter().all(|d| d.d_tag != DT_RELR));
+        assert_eq!(
+            (dynamic.info.rela as u64, dynamic.info.relasz as u64),
+            (table.1, table.2)
+        );
+        assert_eq!(dynamic.info.relaent, 24);
+        // 原有的两项RELA与展开的RELR都在新表中
+        let relocs: Vec<_> = elf
+            .dynrelas
+            .iter()
+            .map(|r| (r.r_offset, r.r_type, r.r_addend))
+            .collect();
+        assert_eq!(
+            relocs,
+            [
+                (0x3fe0, 6, Some(0)),
+                (0x4008, 1, Some(0)),
+                (0x3ff8, 8, Some(0x1000))
+            ]
+        );
+    }
+
+    #[test]
+    fn extract_symbols_from_fixture() {
+        let symbols = SoFixer::new()
+            .extract_symbols(FIXTURE, FIXTURE_BASE)
+            .unwrap();
+        let add = symbols.iter().find(|s| s.name == "fixture_add").unwrap();
+
+        assert_eq!(symbols.len(), 5);
+        assert_eq!(add.value, 0x1004);
+        assert_eq!(add.address, FIXTURE_BASE + 0x1004);
+        assert_eq!(add.sym_type, "FUNC");
     }
 }
@@ -1,5 +1,5 @@
 pub mod dumper;
 pub mod utils;
 
-pub use dumper::{DexDumper, SoDumper};
+pub use dumper::{DexDumper, FileDumper, SoDumper};
 pub use utils::*;
@@ -1,12 +1,14 @@
-mod dumper;
-mod utils;
-
 use anyhow::{anyhow, Result};
 use clap::Parser;
-use std::path::PathBuf;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use dumper::{DexDumper, SoDumper};
-use utils::{get_pid_by_name, list_so_files};
+use tinydump::{
+    default_reader, detect_process_class, get_pid_by_name, list_link_map, open_reader,
+    parse_proc_maps, read_maps, scan_memory_elfs, snapshot_pid, so_files_in, write_snapshot,
+    BreakTrigger, DexDumper, FileDumper, ReaderKind, SoDumper, DEFAULT_BUILD_PROP, SNAPSHOT_MAPS,
+};
 
 #[derive(Parser, Debug)]
 #[command(name = "tinydump")]
@@ -17,6 +19,9 @@ struct Args {
     #[arg(short, long)]
     target: Option<String>,
 
+    #[arg(long, value_parser = parse_hex)]
+    target_addr: Option<u64>,
+
     #[arg(short = 'p', long)]
     attach_pid: Option<u32>,
 
@@ -31,29 +36,190 @@ struct Args {
 
     #[arg(long)]
     list_so: bool,
+
+    #[arg(long)]
+    recover_files: bool,
+
+    #[arg(long, default_value = DEFAULT_BUILD_PROP)]
+    build_prop: PathBuf,
+
+    #[arg(long)]
+    probe_layout: bool,
+
+    #[arg(long)]
+    all: bool,
+
+    #[arg(long)]
+    target_regex: Option<String>,
+
+    #[arg(long)]
+    app_only: bool,
+
+    #[arg(long)]
+    segments: bool,
+
+    #[arg(long)]
+    link_map: bool,
+
+    #[arg(long)]
+    namespaces: bool,
+
+    #[arg(long)]
+    hidden: bool,
+
+    #[arg(long)]
+    hooks: bool,
+
+    #[arg(long)]
+    trace_exec: bool,
+
+    #[arg(long)]
+    soinfo: bool,
+
+    #[arg(long, value_parser = parse_hex, num_args = 0..=1, default_missing_value = "0")]
+    rebase: Option<u64>,
+
+    #[arg(long)]
+    relocs: bool,
+
+    #[arg(long)]
+    jni: bool,
+
+    #[arg(long)]
+    diff: bool,
+
+    #[arg(long)]
+    patched: bool,
+
+    #[arg(long)]
+    json: bool,
+
+    #[arg(long, value_enum)]
+    trigger: Option<BreakTrigger>,
+
+    #[arg(long)]
+    wait: bool,
+
+    #[arg(long, default_value_t = 100)]
+    wait_interval: u64,
+
+    #[arg(long, default_value_t = 500)]
+    wait_delay: u64,
+
+    #[arg(long)]
+    wait_timeout: Option<u64>,
+
+    #[arg(long, value_enum)]
+    reader: Option<ReaderKind>,
+
+    #[arg(long)]
+    snapshot: Option<PathBuf>,
+
+    #[arg(long)]
+    save_snapshot: Option<PathBuf>,
+}
+
+fn parse_hex(value: &str) -> Result<u64, String> {
+    u64::from_str_radix(value.trim_start_matches("0x"), 16).map_err(|e| e.to_string())
+}
+
+fn snapshot_dir(args: &Args) -> Result<&Path> {
+    args.snapshot
+        .as_deref()
+        .ok_or_else(|| anyhow!("Need --snapshot for snapshot reader"))
+}
+
+/// 创建SoDumper并应用所有模式共用的选项
+/// 快照模式从快照目录离线创建，否则按--reader附加到目标进程
+fn new_so_dumper(args: &Args, target_pid: u32, target_name: String) -> Result<SoDumper> {
+    let mut dumper = match args.reader {
+        Some(ReaderKind::Snapshot) => {
+            SoDumper::from_snapshot(snapshot_dir(args)?, target_name, args.output.clone())
+        }
+        _ => SoDumper::new(target_pid, target_name, args.output.clone()),
+    }
+    .map_err(|e| anyhow!("SoDumper failed: {}", e))?;
+    if let Some(kind) = args.reader.filter(|kind| *kind != ReaderKind::Snapshot) {
+        dumper.set_reader(open_reader(kind, target_pid, None)?);
+    }
+    dumper.set_target_addr(args.target_addr);
+    dumper.set_build_prop(args.build_prop.clone());
+    dumper.set_probe_layout(args.probe_layout);
+    dumper.set_segment_mode(args.segments);
+    dumper.set_link_map(args.link_map);
+    dumper.set_namespaces(args.namespaces);
+    dumper.set_rebase(args.rebase);
+    dumper.set_jni_scan(args.jni);
+    dumper.set_json(args.json);
+    Ok(dumper)
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
 
-    let target_pid = if let Some(pid) = args.attach_pid {
+    // ptrace后端只附加主线程，且与断点跟踪同时附加会冲突
+    if args.reader == Some(ReaderKind::Ptrace) && (args.trigger.is_some() || args.trace_exec) {
+        return Err(anyhow!("--reader ptrace cannot be used with --trigger or --trace-exec"));
+    }
+
+    let offline = args.reader == Some(ReaderKind::Snapshot);
+    if offline
+        && (args.trigger.is_some()
+            || args.trace_exec
+            || args.wait
+            || args.recover_files
+            || args.link_map
+            || args.save_snapshot.is_some())
+    {
+        return Err(anyhow!("Snapshot reader only supports offline dump and inspect modes"));
+    }
+
+    let target_pid = if offline {
+        snapshot_pid(snapshot_dir(&args)?)
+    } else if let Some(pid) = args.attach_pid {
         pid
     } else {
         let process_name = args
             .attach_name
+            .as_deref()
             .ok_or_else(|| anyhow!("Need --attach-pid or --attach-name"))?;
-        get_pid_by_name(&process_name)?
+        get_pid_by_name(process_name)?
     };
 
     std::fs::create_dir_all(&args.output)?;
 
-    if args.list_so {
+    if let Some(dir) = &args.save_snapshot {
+        // 保存离线快照模式
+        let reader = match args.reader {
+            Some(kind) => open_reader(kind, target_pid, None)?,
+            None => default_reader(target_pid),
+        };
+        let count = write_snapshot(target_pid, reader.as_ref(), dir)
+            .map_err(|e| anyhow!("Failed to write snapshot: {}", e))?;
+
+        println!("[+] Saved {} mappings to {}", count, dir.display());
+    } else if args.list_so {
         // 列举SO文件模式
         println!("[+] SO list mode");
         println!("[+] PID: {}", target_pid);
+
+        let reader = match args.reader {
+            Some(kind) => open_reader(kind, target_pid, args.snapshot.as_deref())?,
+            None => default_reader(target_pid),
+        };
+        let maps = if offline {
+            read_maps(&snapshot_dir(&args)?.join(SNAPSHOT_MAPS))
+        } else {
+            parse_proc_maps(target_pid)
+        }
+        .map_err(|e| anyhow!("Failed to read memory maps: {}", e))?;
         
-        let so_files = list_so_files(target_pid)
-            .map_err(|e| anyhow!("Failed to list SO files: {}", e))?;
+        let so_files = if args.link_map {
+            list_link_map(target_pid, reader.as_ref(), detect_process_class(target_pid)?)
+        } else {
+            Ok(so_files_in(&maps))
+        }
+        .map_err(|e| anyhow!("Failed to list SO files: {}", e))?;
         
         if so_files.is_empty() {
             println!("[!] No SO files found for PID {}", target_pid);
@@ -69,14 +235,73 @@ fn main() -> Result<()> {
                     so.name, so.start, so.end, size_str, so.permissions);
             }
         }
+
+        let memory_elfs = scan_memory_elfs(&maps, reader.as_ref())
+            .map_err(|e| anyhow!("Failed to scan memory ELF images: {}", e))?;
+
+        if !memory_elfs.is_empty() {
+            println!("[+] Found {} memory-only ELF images:", memory_elfs.len());
+            println!("{:<50} {:<18} {:<18} {:<10} {:<20}", "Name", "Start", "End", "Size", "Permissions");
+            println!("{:-<120}", "");
+
+            for so in memory_elfs {
+                let size_str = format!("{}KB", so.size / 1024);
+
+                println!("{:<50} {:<18x} {:<18x} {:<10} {:<20}",
+                    so.name, so.start, so.end, size_str, so.permissions);
+            }
+            println!("[*] Use --target-addr <Start> to dump a memory-only image");
+        }
+
+        if args.namespaces {
+            // ptrace后端不能重复附加，先释放上面的reader
+            drop(reader);
+            let dumper = new_so_dumper(&args, target_pid, String::new())?;
+            dumper
+                .list_namespaces()
+                .map_err(|e| anyhow!("Failed to list linker namespaces: {}", e))?;
+        }
+    } else if args.soinfo {
+        // soinfo解析模式
+        let dumper = new_so_dumper(&args, target_pid, String::new())?;
+        dumper.inspect_soinfo()?;
+    } else if args.hidden {
+        // 隐藏模块检测模式
+        let dumper = new_so_dumper(&args, target_pid, String::new())?;
+        dumper.report_hidden()?;
+    } else if args.hooks {
+        // GOT/inline hook检测模式
+        let dumper = new_so_dumper(&args, target_pid, String::new())?;
+        dumper.report_hooks()?;
+    } else if args.trace_exec {
+        // mmap/mprotect(PROT_EXEC)跟踪模式
+        let dumper = new_so_dumper(&args, target_pid, String::new())?;
+        dumper.trace_exec()?;
+
+        println!("[+] Exec trace done");
+    } else if args.recover_files {
+        // 恢复已删除/memfd文件模式
+        let file_dumper = FileDumper::new(target_pid, args.output);
+        file_dumper
+            .recover()
+            .map_err(|e| anyhow!("File recovery failed: {}", e))?;
+
+        println!("[+] File recovery done");
     } else if args.dex {
         // DEX模式
         println!("[+] DEX dump mode");
         println!("[+] PID: {}", target_pid);
         println!("[+] Output: {}", args.output.display());
 
-        let mut dex_dumper =
-            DexDumper::new(target_pid as i32).map_err(|e| anyhow!("DexDumper failed: {}", e))?;
+        let mut dex_dumper = if offline {
+            DexDumper::from_snapshot(snapshot_dir(&args)?)
+        } else {
+            DexDumper::new(target_pid as i32)
+        }
+        .map_err(|e| anyhow!("DexDumper failed: {}", e))?;
+        if let Some(kind) = args.reader.filter(|kind| *kind != ReaderKind::Snapshot) {
+            dex_dumper.set_reader(open_reader(kind, target_pid, None)?);
+        }
 
         dex_dumper
             .attach_process()
@@ -87,14 +312,46 @@ fn main() -> Result<()> {
             .map_err(|e| anyhow!("DEX search failed: {}", e))?;
 
         println!("[+] DEX dump done");
+    } else if args.all || args.target_regex.is_some() || args.app_only {
+        // 批量SO dump模式
+        let pattern = args
+            .target_regex
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| anyhow!("Invalid --target-regex: {}", e))?;
+
+        let dumper = new_so_dumper(&args, target_pid, String::new())?;
+        dumper.dump_all(pattern.as_ref(), args.app_only)?;
+
+        println!("[+] Batch SO dump done");
     } else {
         // SO dump模式
-        let target_name = args
-            .target
-            .ok_or_else(|| anyhow!("Need --target for SO dump"))?;
+        let target_name = match (&args.target, args.target_addr) {
+            (Some(target), _) => target.clone(),
+            (None, Some(_)) => "mem".to_string(),
+            (None, None) => return Err(anyhow!("Need --target or --target-addr for SO dump")),
+        };
 
-        let dumper = SoDumper::new(target_pid, target_name, args.output)
-            .map_err(|e| anyhow!("SoDumper failed: {}", e))?;
+        let dumper = new_so_dumper(&args, target_pid, target_name)?;
+        if let Some(trigger) = args.trigger {
+            dumper.dump_on_trigger(trigger)?;
+            println!("[+] SO dump done");
+            return Ok(());
+        }
+        if args.wait {
+            dumper.wait_for_target(
+                Duration::from_millis(args.wait_interval),
+                Duration::from_millis(args.wait_delay),
+                args.wait_timeout.map(Duration::from_secs),
+            )?;
+        }
+        if args.relocs {
+            return dumper.print_relocs();
+        }
+        if args.diff {
+            return dumper.diff(args.patched);
+        }
         dumper.dump()?;
 
         println!("[+] SO dump done");
@@ -0,0 +1,561 @@
+use anyhow::{anyhow, Result};
+use byteorder::{ByteOrder, LittleEndian};
+use goblin::elf::dynamic::DT_NULL;
+use goblin::elf::header::{ELFCLASS32, ELFCLASS64, ELFDATA2LSB, ET_DYN, ET_EXEC};
+use goblin::elf::program_header::PT_LOAD;
+
+pub const ELF_MAGIC: &[u8] = b"\x7fELF";
+pub const PAGE_SIZE: u64 = 0x1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElfClass {
+    Elf32,
+    Elf64,
+}
+
+impl ElfClass {
+    pub fn ptr_size(self) -> usize {
+        match self {
+            ElfClass::Elf32 => 4,
+            ElfClass::Elf64 => 8,
+        }
+    }
+
+    pub fn ehdr_size(self) -> usize {
+        match self {
+            ElfClass::Elf32 => 0x34,
+            ElfClass::Elf64 => 0x40,
+        }
+    }
+
+    pub fn phdr_size(self) -> usize {
+        match self {
+            ElfClass::Elf32 => 0x20,
+            ElfClass::Elf64 => 0x38,
+        }
+    }
+
+    pub fn shdr_size(self) -> usize {
+        match self {
+            ElfClass::Elf32 => 0x28,
+            ElfClass::Elf64 => 0x40,
+        }
+    }
+
+    pub fn sym_size(self) -> usize {
+        match self {
+            ElfClass::Elf32 => 0x10,
+            ElfClass::Elf64 => 0x18,
+        }
+    }
+
+    pub fn dyn_size(self) -> usize {
+        self.ptr_size() * 2
+    }
+
+    pub fn read_word(self, data: &[u8], offset: usize) -> Option<u64> {
+        match self {
+            ElfClass::Elf32 => read_u32(data, offset).map(u64::from),
+            ElfClass::Elf64 => read_u64(data, offset),
+        }
+    }
+
+    pub fn write_word(self, data: &mut [u8], offset: usize, value: u64) -> Option<()> {
+        match self {
+            ElfClass::Elf32 => write_u32(data, offset, value as u32),
+            ElfClass::Elf64 => write_u64(data, offset, value),
+        }
+    }
+}
+
+impl std::fmt::Display for ElfClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ElfClass::Elf32 => write!(f, "32-bit"),
+            ElfClass::Elf64 => write!(f, "64-bit"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ElfHeader {
+    pub class: ElfClass,
+    pub e_type: u16,
+    pub e_machine: u16,
+    pub e_entry: u64,
+    pub e_phoff: u64,
+    pub e_shoff: u64,
+    pub e_phentsize: u16,
+    pub e_phnum: u16,
+    pub e_shentsize: u16,
+    pub e_shnum: u16,
+    pub e_shstrndx: u16,
+}
+
+impl ElfHeader {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < 0x34 || &data[..4] != ELF_MAGIC {
+            return Err(anyhow!("Not an ELF image"));
+        }
+        if data[5] != ELFDATA2LSB {
+            return Err(anyhow!("Only little-endian ELF images are supported"));
+        }
+
+        let class = match data[4] {
+            ELFCLASS32 => ElfClass::Elf32,
+            ELFCLASS64 => ElfClass::Elf64,
+            other => return Err(anyhow!("Unknown ELF class {}", other)),
+        };
+        if data.len() < class.ehdr_size() {
+            return Err(anyhow!("Truncated ELF header"));
+        }
+
+        let ptr = class.ptr_size();
+        let e_entry = class.read_word(data, 0x18).unwrap_or(0);
+        let e_phoff = class.read_word(data, 0x18 + ptr).unwrap_or(0);
+        let e_shoff = class.read_word(data, 0x18 + ptr * 2).unwrap_or(0);
+        let tail = 0x18 + ptr * 3 + 4;
+
+        Ok(Self {
+            class,
+            e_type: LittleEndian::read_u16(&data[0x10..]),
+            e_machine: LittleEndian::read_u16(&data[0x12..]),
+            e_entry,
+            e_phoff,
+            e_shoff,
+            e_phentsize: LittleEndian::read_u16(&data[tail + 2..]),
+            e_phnum: LittleEndian::read_u16(&data[tail + 4..]),
+            e_shentsize: LittleEndian::read_u16(&data[tail + 6..]),
+            e_shnum: LittleEndian::read_u16(&data[tail + 8..]),
+            e_shstrndx: LittleEndian::read_u16(&data[tail + 10..]),
+        })
+    }
+
+    /// 修改节区头相关字段
+    pub fn patch_section_info(
+        &self,
+        data: &mut [u8],
+        shoff: u64,
+        shnum: u16,
+        shstrndx: u16,
+    ) -> Option<()> {
+        let ptr = self.class.ptr_size();
+        let tail = 0x18 + ptr * 3 + 4;
+        self.class.write_word(data, 0x18 + ptr * 2, shoff)?;
+        write_u16(data, tail + 6, self.class.shdr_size() as u16)?;
+        write_u16(data, tail + 8, shnum)?;
+        write_u16(data, tail + 10, shstrndx)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProgramHeader {
+    pub p_type: u32,
+    pub p_flags: u32,
+    pub p_offset: u64,
+    pub p_vaddr: u64,
+    pub p_paddr: u64,
+    pub p_filesz: u64,
+    pub p_memsz: u64,
+    pub p_align: u64,
+}
+
+impl ProgramHeader {
+    pub fn parse(data: &[u8], offset: usize, class: ElfClass) -> Option<Self> {
+        if offset + class.phdr_size() > data.len() {
+            return None;
+        }
+        let d = &data[offset..];
+        Some(match class {
+            ElfClass::Elf32 => Self {
+                p_type: LittleEndian::read_u32(d),
+                p_offset: LittleEndian::read_u32(&d[0x04..]) as u64,
+                p_vaddr: LittleEndian::read_u32(&d[0x08..]) as u64,
+                p_paddr: LittleEndian::read_u32(&d[0x0c..]) as u64,
+                p_filesz: LittleEndian::read_u32(&d[0x10..]) as u64,
+                p_memsz: LittleEndian::read_u32(&d[0x14..]) as u64,
+                p_flags: LittleEndian::read_u32(&d[0x18..]),
+                p_align: LittleEndian::read_u32(&d[0x1c..]) as u64,
+            },
+            ElfClass::Elf64 => Self {
+                p_type: LittleEndian::read_u32(d),
+                p_flags: LittleEndian::read_u32(&d[0x04..]),
+                p_offset: LittleEndian::read_u64(&d[0x08..]),
+                p_vaddr: LittleEndian::read_u64(&d[0x10..]),
+                p_paddr: LittleEndian::read_u64(&d[0x18..]),
+                p_filesz: LittleEndian::read_u64(&d[0x20..]),
+                p_memsz: LittleEndian::read_u64(&d[0x28..]),
+                p_align: LittleEndian::read_u64(&d[0x30..]),
+            },
+        })
+    }
+
+    pub fn write(&self, data: &mut [u8], offset: usize, class: ElfClass) -> Option<()> {
+        if offset + class.phdr_size() > data.len() {
+            return None;
+        }
+        let d = &mut data[offset..];
+        match class {
+            ElfClass::Elf32 => {
+                LittleEndian::write_u32(d, self.p_type);
+                LittleEndian::write_u32(&mut d[0x04..], self.p_offset as u32);
+                LittleEndian::write_u32(&mut d[0x08..], self.p_vaddr as u32);
+                LittleEndian::write_u32(&mut d[0x0c..], self.p_paddr as u32);
+                LittleEndian::write_u32(&mut d[0x10..], self.p_filesz as u32);
+                LittleEndian::write_u32(&mut d[0x14..], self.p_memsz as u32);
+                LittleEndian::write_u32(&mut d[0x18..], self.p_flags);
+                LittleEndian::write_u32(&mut d[0x1c..], self.p_align as u32);
+            }
+            ElfClass::Elf64 => {
+                LittleEndian::write_u32(d, self.p_type);
+                LittleEndian::write_u32(&mut d[0x04..], self.p_flags);
+                LittleEndian::write_u64(&mut d[0x08..], self.p_offset);
+                LittleEndian::write_u64(&mut d[0x10..], self.p_vaddr);
+                LittleEndian::write_u64(&mut d[0x18..], self.p_paddr);
+                LittleEndian::write_u64(&mut d[0x20..], self.p_filesz);
+                LittleEndian::write_u64(&mut d[0x28..], self.p_memsz);
+                LittleEndian::write_u64(&mut d[0x30..], self.p_align);
+            }
+        }
+        Some(())
+    }
+
+    pub fn is_load(&self) -> bool {
+        self.p_type == PT_LOAD
+    }
+}
+
+/// 解析程序头表，data需要从ELF头开始
+pub fn parse_program_headers(data: &[u8], header: &ElfHeader) -> Result<Vec<ProgramHeader>> {
+    let entsize = header.class.phdr_size();
+    if header.e_phnum == 0 || header.e_phentsize as usize != entsize {
+        return Err(anyhow!("Invalid program header table"));
+    }
+
+    (0..header.e_phnum as usize)
+        .map(|i| {
+            ProgramHeader::parse(data, header.e_phoff as usize + i * entsize, header.class)
+                .ok_or_else(|| anyhow!("Program header {} out of range", i))
+        })
+        .collect()
+}
+
+/// 判断ELF头和程序头是否像一个真实加载的镜像
+pub fn is_plausible_image(header: &ElfHeader, phdrs: &[ProgramHeader]) -> bool {
+    const MAX_IMAGE_SIZE: u64 = 0x40000000;
+
+    if header.e_type != ET_DYN && header.e_type != ET_EXEC {
+        return false;
+    }
+    if header.e_phnum == 0 || header.e_phnum > 64 || header.e_phoff >= PAGE_SIZE {
+        return false;
+    }
+
+    let mut loads = phdrs.iter().filter(|p| p.is_load()).peekable();
+    if loads.peek().is_none() {
+        return false;
+    }
+    let sane_loads = loads.all(|p| {
+        p.p_memsz >= p.p_filesz
+            && p.p_memsz < MAX_IMAGE_SIZE
+            && p.p_vaddr.checked_add(p.p_memsz).is_some()
+            && (p.p_align == 0 || p.p_align.is_power_of_two())
+    });
+
+    sane_loads && load_span(phdrs) < MAX_IMAGE_SIZE
+}
+
+/// 所有PT_LOAD段中最小的页对齐虚拟地址
+pub fn min_load_vaddr(phdrs: &[ProgramHeader]) -> u64 {
+    phdrs
+        .iter()
+        .filter(|p| p.is_load())
+        .map(|p| p.p_vaddr & !(PAGE_SIZE - 1))
+        .min()
+        .unwrap_or(0)
+}
+
+/// 所有PT_LOAD段覆盖的内存大小
+pub fn load_span(phdrs: &[ProgramHeader]) -> u64 {
+    let end = phdrs
+        .iter()
+        .filter(|p| p.is_load())
+        .map(|p| {
+            p.p_vaddr
+                .saturating_add(p.p_memsz)
+                .saturating_add(PAGE_SIZE - 1)
+                & !(PAGE_SIZE - 1)
+        })
+        .max()
+        .unwrap_or(0);
+    end.saturating_sub(min_load_vaddr(phdrs))
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Sym {
+    pub st_name: u32,
+    pub st_info: u8,
+    pub st_other: u8,
+    pub st_shndx: u16,
+    pub st_value: u64,
+    pub st_size: u64,
+}
+
+/// 读取Elf32_Sym/Elf64_Sym，两者字段顺序不同
+pub fn read_sym(data: &[u8], offset: usize, class: ElfClass) -> Option<Sym> {
+    match class {
+        ElfClass::Elf32 => Some(Sym {
+            st_name: read_u32(data, offset)?,
+            st_value: read_u32(data, offset + 4)? as u64,
+            st_size: read_u32(data, offset + 8)? as u64,
+            st_info: *data.get(offset + 12)?,
+            st_other: *data.get(offset + 13)?,
+            st_shndx: read_u16(data, offset + 14)?,
+        }),
+        ElfClass::Elf64 => Some(Sym {
+            st_name: read_u32(data, offset)?,
+            st_info: *data.get(offset + 4)?,
+            st_other: *data.get(offset + 5)?,
+            st_shndx: read_u16(data, offset + 6)?,
+            st_value: read_u64(data, offset + 8)?,
+            st_size: read_u64(data, offset + 16)?,
+        }),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Dyn {
+    pub d_tag: u64,
+    pub d_val: u64,
+}
+
+/// 解析动态段，遇到DT_NULL结束
+pub fn parse_dynamic(data: &[u8], class: ElfClass) -> Vec<Dyn> {
+    let mut entries = Vec::new();
+    let entsize = class.dyn_size();
+    let mut offset = 0;
+
+    while let (Some(d_tag), Some(d_val)) = (
+        class.read_word(data, offset),
+        class.read_word(data, offset + class.ptr_size()),
+    ) {
+        if d_tag == DT_NULL {
+            break;
+        }
+        entries.push(Dyn { d_tag, d_val });
+        offset += entsize;
+    }
+
+    entries
+}
+
+/// 从PT_NOTE内容中提取NT_GNU_BUILD_ID，返回十六进制字符串
+pub fn parse_build_id(notes: &[u8]) -> Option<String> {
+    const NT_GNU_BUILD_ID: u32 = 3;
+    let mut offset = 0;
+
+    while let (Some(namesz), Some(descsz), Some(n_type)) = (
+        read_u32(notes, offset),
+        read_u32(notes, offset + 4),
+        read_u32(notes, offset + 8),
+    ) {
+        let name = offset + 12;
+        let desc = name + ((namesz as usize + 3) & !3);
+        let next = desc + ((descsz as usize + 3) & !3);
+
+        if n_type == NT_GNU_BUILD_ID && notes.get(name..name + 3) == Some(b"GNU") {
+            let id = notes.get(desc..desc + descsz as usize)?;
+            return Some(id.iter().map(|b| format!("{:02x}", b)).collect());
+        }
+        offset = next;
+    }
+
+    None
+}
+
+pub fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2).map(LittleEndian::read_u16)
+}
+
+pub fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4).map(LittleEndian::read_u32)
+}
+
+pub fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    data.get(offset..offset + 8).map(LittleEndian::read_u64)
+}
+
+pub fn write_u16(data: &mut [u8], offset: usize, value: u16) -> Option<()> {
+    data.get_mut(offset..offset + 2)
+        .map(|d| LittleEndian::write_u16(d, value))
+}
+
+pub fn write_u32(data: &mut [u8], offset: usize, value: u32) -> Option<()> {
+    data.get_mut(offset..offset + 4)
+        .map(|d| LittleEndian::write_u32(d, value))
+}
+
+pub fn write_u64(data: &mut [u8], offset: usize, value: u64) -> Option<()> {
+    data.get_mut(offset..offset + 8)
+        .map(|d| LittleEndian::write_u64(d, value))
+}
+
+/// 读取以\0结尾的字符串
+pub fn read_cstr(data: &[u8], offset: usize) -> Option<String> {
+    let bytes = data.get(offset..)?;
+    let end = bytes.iter().position(|&b| b == 0)?;
+    Some(String::from_utf8_lossy(&bytes[..end]).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use goblin::elf::dynamic::{DT_HASH, DT_STRSZ};
+    use goblin::elf::header::EM_X86_64;
+
+    const FIXTURE: &[u8] = include_bytes!("../../tests/fixtures/libfixture_x86_64_dump.bin");
+
+    #[test]
+    fn parse_fixture_header() {
+        let header = ElfHeader::parse(FIXTURE).unwrap();
+
+        assert_eq!(header.class, ElfClass::Elf64);
+        assert_eq!(header.e_type, ET_DYN);
+        assert_eq!(header.e_machine, EM_X86_64);
+        assert_eq!(header.e_phoff, 0x40);
+        assert_eq!(header.e_phnum, 9);
+    }
+
+    #[test]
+    fn parse_header_rejects_invalid_images() {
+        assert!(ElfHeader::parse(&FIXTURE[..0x20]).is_err());
+        assert!(ElfHeader::parse(&[0u8; 0x40]).is_err());
+
+        let mut big_endian = FIXTURE[..0x40].to_vec();
+        big_endian[5] = 2;
+        assert!(ElfHeader::parse(&big_endian).is_err());
+    }
+
+    #[test]
+    fn parse_fixture_program_headers() {
+        let header = ElfHeader::parse(FIXTURE).unwrap();
+        let phdrs = parse_program_headers(FIXTURE, &header).unwrap();
+        let loads: Vec<_> = phdrs
+            .iter()
+            .filter(|p| p.is_load())
+            .map(|p| (p.p_vaddr, p.p_memsz))
+            .collect();
+
+        assert_eq!(
+            loads,
+            [(0, 0x3d0), (0x1000, 0x12), (0x2000, 0x7c), (0x3ef0, 0x120)]
+        );
+        assert_eq!(min_load_vaddr(&phdrs), 0);
+        assert_eq!(load_span(&phdrs), 0x5000);
+        assert!(is_plausible_image(&header, &phdrs));
+    }
+
+    #[test]
+    fn program_header_roundtrip() {
+        for class in [ElfClass::Elf32, ElfClass::Elf64] {
+            let phdr = ProgramHeader {
+                p_type: PT_LOAD,
+                p_flags: 5,
+                p_offset: 0x1000,
+                p_vaddr: 0x2000,
+                p_paddr: 0x2000,
+                p_filesz: 0x300,
+                p_memsz: 0x400,
+                p_align: 0x1000,
+            };
+            let mut data = vec![0u8; class.phdr_size()];
+            phdr.write(&mut data, 0, class).unwrap();
+            let parsed = ProgramHeader::parse(&data, 0, class).unwrap();
+
+            assert_eq!(parsed.p_flags, 5);
+            assert_eq!(parsed.p_vaddr, 0x2000);
+            assert_eq!(parsed.p_memsz, 0x400);
+            assert!(ProgramHeader::parse(&data, 1, class).is_none());
+        }
+    }
+
+    #[test]
+    fn implausible_load_segments() {
+        let header = ElfHeader::parse(FIXTURE).unwrap();
+        let mut phdrs = parse_program_headers(FIXTURE, &header).unwrap();
+        let memsz = phdrs[3].p_memsz;
+        phdrs[3].p_memsz = 0x8000_0000;
+
+        assert!(!is_plausible_image(&header, &phdrs));
+
+        phdrs[3].p_memsz = memsz;
+        assert!(is_plausible_image(&header, &phdrs));
+
+        // 整体移到地址空间顶端：跨度不变，但最后一段的结束地址溢出
+        let end = phdrs
+            .iter()
+            .filter(|p| p.is_load())
+            .map(|p| p.p_vaddr + p.p_memsz)
+            .max()
+            .unwrap();
+        let shift = u64::MAX - end + 0x10;
+        for phdr in phdrs.iter_mut().filter(|p| p.is_load()) {
+            phdr.p_vaddr += shift;
+        }
+        assert!(!is_plausible_image(&header, &phdrs));
+    }
+
+    #[test]
+    fn parse_dynamic_stops_at_null() {
+        let class = ElfClass::Elf32;
+        let mut data = vec![0u8; 32];
+        for (i, (tag, val)) in [
+            (DT_HASH, 0x100),
+            (DT_STRSZ, 0x20),
+            (DT_NULL, 0),
+            (DT_HASH, 1),
+        ]
+        .iter()
+        .enumerate()
+        {
+            class.write_word(&mut data, i * 8, *tag).unwrap();
+            class.write_word(&mut data, i * 8 + 4, *val).unwrap();
+        }
+        let dynamic = parse_dynamic(&data, class);
+
+        assert_eq!(dynamic.len(), 2);
+        assert_eq!((dynamic[1].d_tag, dynamic[1].d_val), (DT_STRSZ, 0x20));
+    }
+
+    #[test]
+    fn read_sym_field_order() {
+        let mut sym32 = vec![0u8; 16];
+        write_u32(&mut sym32, 0, 7).unwrap();
+        write_u32(&mut sym32, 4, 0x1234).unwrap();
+        write_u32(&mut sym32, 8, 0x10).unwrap();
+        sym32[12] = 0x12;
+        write_u16(&mut sym32, 14, 9).unwrap();
+
+        let sym = read_sym(&sym32, 0, ElfClass::Elf32).unwrap();
+        assert_eq!((sym.st_name, sym.st_value, sym.st_size), (7, 0x1234, 0x10));
+        assert_eq!((sym.st_info, sym.st_shndx), (0x12, 9));
+
+        // fixture中.dynsym的第1项为fixture_add
+        let sym = read_sym(FIXTURE, 0x2c8 + 0x18, ElfClass::Elf64).unwrap();
+        assert_eq!((sym.st_value, sym.st_size), (0x1004, 13));
+        assert_eq!(
+            read_cstr(FIXTURE, 0x358 + sym.st_name as usize).as_deref(),
+            Some("fixture_add")
+        );
+        assert!(read_sym(FIXTURE, FIXTURE.len() - 8, ElfClass::Elf64).is_none());
+    }
+
+    #[test]
+    fn parse_fixture_build_id() {
+        assert_eq!(
+            parse_build_id(&FIXTURE[0x238..0x25c]).as_deref(),
+            Some("4dc6cccc63013ab6af10fb78d9ad99d68e494f6e")
+        );
+        assert_eq!(parse_build_id(&[0u8; 12]), None);
+    }
+}
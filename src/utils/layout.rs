@@ -0,0 +1,269 @@
+use anyhow::{anyhow, Result};
+use goblin::elf::header::{EM_386, EM_ARM};
+use std::path::Path;
+use std::process::Command;
+
+use super::elf::ElfClass;
+
+pub const DEFAULT_BUILD_PROP: &str = "/system/build.prop";
+
+/// bionic soinfo结构体中各字段的偏移
+#[derive(Debug)]
+pub struct SoInfoLayout {
+    pub name: &'static str,
+    pub class: ElfClass,
+    pub machine: Option<u16>,
+    pub min_api: u32,
+    pub max_api: u32,
+    pub phdr: usize,
+    pub phnum: usize,
+    pub base: usize,
+    pub size: usize,
+    pub dynamic: usize,
+    pub next: usize,
+    pub flags: usize,
+    pub strtab: usize,
+    pub symtab: usize,
+    pub init_array: usize,
+    pub init_array_count: usize,
+    pub fini_array: usize,
+    pub fini_array_count: usize,
+    pub constructors_called: usize,
+    pub load_bias: usize,
+    /// realpath_（std::string）的偏移，API 23之前的soinfo没有该字段
+    pub realpath: Option<usize>,
+}
+
+impl SoInfoLayout {
+    pub fn supports_api(&self, api: u32) -> bool {
+        (self.min_api..=self.max_api).contains(&api)
+    }
+
+    pub fn matches(&self, class: ElfClass, machine: Option<u16>) -> bool {
+        self.class == class
+            && match (self.machine, machine) {
+                (Some(expected), Some(machine)) => expected == machine,
+                _ => true,
+            }
+    }
+}
+
+// load_bias之前的字段从API 21起没有变化，32位的soinfo保留了old_name_和unused字段，ARM上多出ARM_exidx两个字段
+// realpath_在API 23加入，位于version_、st_dev_/st_ino_、children_/parents_、gnu hash等字段和soname_之后
+// 32位的dev_t和ino_t为4字节，x86上off64_t按4字节对齐
+pub const SOINFO_LAYOUTS: &[SoInfoLayout] = &[
+    SoInfoLayout {
+        name: "lp64-lollipop",
+        class: ElfClass::Elf64,
+        machine: None,
+        min_api: 21,
+        max_api: 22,
+        phdr: 0x0,
+        phnum: 0x8,
+        base: 0x10,
+        size: 0x18,
+        dynamic: 0x20,
+        next: 0x28,
+        flags: 0x30,
+        strtab: 0x38,
+        symtab: 0x40,
+        init_array: 0x98,
+        init_array_count: 0xa0,
+        fini_array: 0xa8,
+        fini_array_count: 0xb0,
+        constructors_called: 0xf8,
+        load_bias: 0x100,
+        realpath: None,
+    },
+    SoInfoLayout {
+        name: "lp64",
+        class: ElfClass::Elf64,
+        machine: None,
+        min_api: 23,
+        max_api: u32::MAX,
+        phdr: 0x0,
+        phnum: 0x8,
+        base: 0x10,
+        size: 0x18,
+        dynamic: 0x20,
+        next: 0x28,
+        flags: 0x30,
+        strtab: 0x38,
+        symtab: 0x40,
+        init_array: 0x98,
+        init_array_count: 0xa0,
+        fini_array: 0xa8,
+        fini_array_count: 0xb0,
+        constructors_called: 0xf8,
+        load_bias: 0x100,
+        realpath: Some(0x1a0),
+    },
+    SoInfoLayout {
+        name: "arm32-legacy",
+        class: ElfClass::Elf32,
+        machine: Some(EM_ARM),
+        min_api: 0,
+        max_api: 22,
+        phdr: 0x80,
+        phnum: 0x84,
+        base: 0x8c,
+        size: 0x90,
+        dynamic: 0x98,
+        next: 0xa4,
+        flags: 0xa8,
+        strtab: 0xac,
+        symtab: 0xb0,
+        init_array: 0xe0,
+        init_array_count: 0xe4,
+        fini_array: 0xe8,
+        fini_array_count: 0xec,
+        constructors_called: 0x118,
+        load_bias: 0x11c,
+        realpath: None,
+    },
+    SoInfoLayout {
+        name: "arm32",
+        class: ElfClass::Elf32,
+        machine: Some(EM_ARM),
+        min_api: 23,
+        max_api: u32::MAX,
+        phdr: 0x80,
+        phnum: 0x84,
+        base: 0x8c,
+        size: 0x90,
+        dynamic: 0x98,
+        next: 0xa4,
+        flags: 0xa8,
+        strtab: 0xac,
+        symtab: 0xb0,
+        init_array: 0xe0,
+        init_array_count: 0xe4,
+        fini_array: 0xe8,
+        fini_array_count: 0xec,
+        constructors_called: 0x118,
+        load_bias: 0x11c,
+        realpath: Some(0x17c),
+    },
+    SoInfoLayout {
+        name: "x86-legacy",
+        class: ElfClass::Elf32,
+        machine: Some(EM_386),
+        min_api: 0,
+        max_api: 22,
+        phdr: 0x80,
+        phnum: 0x84,
+        base: 0x8c,
+        size: 0x90,
+        dynamic: 0x98,
+        next: 0xa4,
+        flags: 0xa8,
+        strtab: 0xac,
+        symtab: 0xb0,
+        init_array: 0xe0,
+        init_array_count: 0xe4,
+        fini_array: 0xe8,
+        fini_array_count: 0xec,
+        constructors_called: 0x110,
+        load_bias: 0x114,
+        realpath: None,
+    },
+    SoInfoLayout {
+        name: "x86",
+        class: ElfClass::Elf32,
+        machine: Some(EM_386),
+        min_api: 23,
+        max_api: u32::MAX,
+        phdr: 0x80,
+        phnum: 0x84,
+        base: 0x8c,
+        size: 0x90,
+        dynamic: 0x98,
+        next: 0xa4,
+        flags: 0xa8,
+        strtab: 0xac,
+        symtab: 0xb0,
+        init_array: 0xe0,
+        init_array_count: 0xe4,
+        fini_array: 0xe8,
+        fini_array_count: 0xec,
+        constructors_called: 0x110,
+        load_bias: 0x114,
+        realpath: Some(0x174),
+    },
+];
+
+/// 根据位数、架构和API版本选择soinfo布局，API未知时使用最新的布局
+pub fn find_layout(
+    class: ElfClass,
+    machine: Option<u16>,
+    api: Option<u32>,
+) -> Option<&'static SoInfoLayout> {
+    let mut candidates = SOINFO_LAYOUTS.iter().filter(|l| l.matches(class, machine));
+    match api {
+        Some(api) => candidates.find(|l| l.supports_api(api)),
+        None => candidates.max_by_key(|l| l.min_api),
+    }
+}
+
+/// 读取Android SDK版本，优先解析build.prop，失败时调用getprop
+pub fn get_android_sdk(build_prop: &Path) -> Result<u32> {
+    if let Ok(content) = std::fs::read_to_string(build_prop) {
+        for line in content.lines() {
+            if let Some(value) = line.trim().strip_prefix("ro.build.version.sdk=") {
+                return value
+                    .trim()
+                    .parse::<u32>()
+                    .map_err(|_| anyhow!("Invalid ro.build.version.sdk: {}", value));
+            }
+        }
+    }
+
+    let output = Command::new("getprop")
+        .arg("ro.build.version.sdk")
+        .output()
+        .map_err(|_| anyhow!("Failed to read SDK version from {}", build_prop.display()))?;
+    let value = String::from_utf8_lossy(&output.stdout);
+    value
+        .trim()
+        .parse::<u32>()
+        .map_err(|_| anyhow!("Invalid ro.build.version.sdk: {}", value.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use goblin::elf::header::EM_AARCH64;
+
+    #[test]
+    fn layouts_by_api() {
+        let lollipop = find_layout(ElfClass::Elf64, Some(EM_AARCH64), Some(22)).unwrap();
+        let marshmallow = find_layout(ElfClass::Elf64, Some(EM_AARCH64), Some(23)).unwrap();
+
+        assert_eq!(lollipop.name, "lp64-lollipop");
+        assert_eq!(lollipop.realpath, None);
+        assert_eq!(marshmallow.name, "lp64");
+        assert_eq!(marshmallow.realpath, Some(0x1a0));
+        assert!(find_layout(ElfClass::Elf64, None, Some(19)).is_none());
+    }
+
+    #[test]
+    fn layouts_by_machine() {
+        let arm = find_layout(ElfClass::Elf32, Some(EM_ARM), Some(19)).unwrap();
+        let x86 = find_layout(ElfClass::Elf32, Some(EM_386), None).unwrap();
+
+        assert_eq!(arm.name, "arm32-legacy");
+        assert_eq!(arm.realpath, None);
+        assert_eq!(x86.name, "x86");
+        assert_eq!(x86.constructors_called, 0x110);
+    }
+
+    #[test]
+    fn layout_ranges_do_not_overlap() {
+        for (i, a) in SOINFO_LAYOUTS.iter().enumerate() {
+            for b in &SOINFO_LAYOUTS[i + 1..] {
+                let same_target = a.class == b.class && a.machine == b.machine;
+                assert!(!same_target || a.max_api < b.min_api || b.max_api < a.min_api);
+            }
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use anyhow::{anyhow, Result};
+use goblin::elf::dynamic::DT_DEBUG;
+use goblin::elf::header::ET_EXEC;
+use goblin::elf::program_header::PT_DYNAMIC;
+use goblin::elf::Elf;
+
+use super::elf::{min_load_vaddr, parse_dynamic, parse_program_headers, ElfClass, ElfHeader};
+use super::memory::MemoryReader;
+use super::process::{parse_proc_maps, SoFileInfo};
+use super::types::MemoryMapping;
+
+/// link_map链表中的一项
+#[derive(Debug, Clone)]
+pub struct LinkMapEntry {
+    pub address: u64,
+    pub l_addr: u64,
+    pub l_name: String,
+    pub l_ld: u64,
+}
+
+fn read_word(reader: &dyn MemoryReader, class: ElfClass, address: u64) -> Result<u64> {
+    let data = reader.read_bytes(address, class.ptr_size())?;
+    class
+        .read_word(&data, 0)
+        .ok_or_else(|| anyhow!("Failed to read pointer at {:#x}", address))
+}
+
+fn read_string(reader: &dyn MemoryReader, address: u64) -> String {
+    const MAX_NAME: usize = 0x400;
+    let (data, _) = reader.read_tolerant(address, MAX_NAME);
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    String::from_utf8_lossy(&data[..end]).into_owned()
+}
+
+fn image_base(mappings: &[MemoryMapping], pathname: &str) -> Option<u64> {
+    mappings
+        .iter()
+        .filter(|m| m.pathname == pathname)
+        .map(|m| m.start)
+        .min()
+}
+
+// 可执行文件的DT_DEBUG在加载后被ld.so填为&_r_debug
+fn find_r_debug_by_dt_debug(
+    pid: u32,
+    reader: &dyn MemoryReader,
+    mappings: &[MemoryMapping],
+) -> Result<u64> {
+    let exe = std::fs::read_link(format!("/proc/{}/exe", pid))?;
+    let exe = exe.to_string_lossy();
+    let base = image_base(mappings, &exe)
+        .ok_or_else(|| anyhow!("Could not find {} in process maps", exe))?;
+
+    let page = reader.read_bytes(base, 0x1000)?;
+    let header = ElfHeader::parse(&page)?;
+    let phdrs = parse_program_headers(&page, &header)?;
+    let load_bias = if header.e_type == ET_EXEC {
+        0
+    } else {
+        base.checked_sub(min_load_vaddr(&phdrs))
+            .ok_or_else(|| anyhow!("Invalid load bias of {}", exe))?
+    };
+
+    let dynamic = phdrs
+        .iter()
+        .find(|p| p.p_type == PT_DYNAMIC)
+        .ok_or_else(|| anyhow!("No PT_DYNAMIC in {}", exe))?;
+    let data = reader.read_bytes(load_bias + dynamic.p_vaddr, dynamic.p_memsz as usize)?;
+
+    parse_dynamic(&data, header.class)
+        .iter()
+        .find(|d| d.d_tag == DT_DEBUG && d.d_val != 0)
+        .map(|d| d.d_val)
+        .ok_or_else(|| anyhow!("DT_DEBUG is not set in {}", exe))
+}
+
+// 从ld-linux或linker的符号表中查找_r_debug
+fn find_r_debug_by_symbol(pid: u32, mappings: &[MemoryMapping]) -> Result<u64> {
+    let loader = mappings
+        .iter()
+        .map(|m| m.pathname.as_str())
+        .find(|p| {
+            p.contains("/ld-linux")
+                || p.contains("/ld-musl")
+                || p.ends_with("/linker64")
+                || p.ends_with("/linker")
+        })
+        .ok_or_else(|| anyhow!("Could not find dynamic loader in process maps"))?;
+    let base = image_base(mappings, loader)
+        .ok_or_else(|| anyhow!("Could not find base of {} in process maps", loader))?;
+
+    let buffer = std::fs::read(format!("/proc/{}/root{}", pid, loader))
+        .or_else(|_| std::fs::read(loader))?;
+    let elf = Elf::parse(&buffer)?;
+    let min_vaddr = elf
+        .program_headers
+        .iter()
+        .filter(|p| p.p_type == goblin::elf::program_header::PT_LOAD)
+        .map(|p| p.p_vaddr & !0xfff)
+        .min()
+        .unwrap_or(0);
+
+    let symbol = elf
+        .dynsyms
+        .iter()
+        .map(|sym| (sym, elf.dynstrtab.get_at(sym.st_name)))
+        .chain(
+            elf.syms
+                .iter()
+                .map(|sym| (sym, elf.strtab.get_at(sym.st_name))),
+        )
+        .find(|(_, name)| matches!(name, Some("_r_debug") | Some("__dl__r_debug")))
+        .map(|(sym, _)| sym)
+        .ok_or_else(|| anyhow!("Could not find _r_debug in {}", loader))?;
+
+    base.checked_sub(min_vaddr)
+        .and_then(|load_bias| load_bias.checked_add(symbol.st_value))
+        .ok_or_else(|| anyhow!("Invalid _r_debug address in {}", loader))
+}
+
+/// 定位目标进程的_r_debug，优先DT_DEBUG，失败时查找loader符号
+pub fn find_r_debug(pid: u32, reader: &dyn MemoryReader) -> Result<u64> {
+    let mappings = parse_proc_maps(pid)?;
+    match find_r_debug_by_dt_debug(pid, reader, &mappings) {
+        Ok(r_debug) => Ok(r_debug),
+        Err(e) => {
+            println!("[*] {}, trying loader symbols", e);
+            find_r_debug_by_symbol(pid, &mappings)
+        }
+    }
+}
+
+/// 遍历r_debug.r_map链表
+pub fn walk_link_map(
+    reader: &dyn MemoryReader,
+    class: ElfClass,
+    r_debug: u64,
+) -> Result<Vec<LinkMapEntry>> {
+    const MAX_ENTRIES: usize = 4096;
+    let ptr = class.ptr_size() as u64;
+
+    // struct r_debug { int r_version; struct link_map *r_map; ... }
+    let mut current = read_word(reader, class, r_debug + ptr)?;
+    let mut entries = Vec::new();
+
+    while current != 0 && entries.len() < MAX_ENTRIES {
+        // struct link_map { l_addr, l_name, l_ld, l_next, l_prev }
+        let l_addr = read_word(reader, class, current)?;
+        let name_ptr = read_word(reader, class, current + ptr)?;
+        let l_ld = read_word(reader, class, current + ptr * 2)?;
+        let next = read_word(reader, class, current + ptr * 3)?;
+
+        entries.push(LinkMapEntry {
+            address: current,
+            l_addr,
+            l_name: if name_ptr != 0 {
+                read_string(reader, name_ptr)
+            } else {
+                String::new()
+            },
+            l_ld,
+        });
+        current = next;
+    }
+
+    Ok(entries)
+}
+
+/// 通过link_map列举已加载的库，结果与list_so_files格式一致
+pub fn list_link_map(
+    pid: u32,
+    reader: &dyn MemoryReader,
+    class: ElfClass,
+) -> Result<Vec<SoFileInfo>> {
+    let r_debug = find_r_debug(pid, reader)?;
+    println!("[+] _r_debug: {:#x}", r_debug);
+
+    let mappings = parse_proc_maps(pid)?;
+    let mut so_files = Vec::new();
+
+    for entry in walk_link_map(reader, class, r_debug)? {
+        // 以l_ld所在映射的文件名为准，主程序和vdso的l_name可能为空
+        let Some(pathname) = mappings
+            .iter()
+            .find(|m| entry.l_ld >= m.start && entry.l_ld < m.end)
+            .map(|m| m.pathname.clone())
+        else {
+            continue;
+        };
+        let image: Vec<&MemoryMapping> =
+            mappings.iter().filter(|m| m.pathname == pathname).collect();
+        let start = image.iter().map(|m| m.start).min().unwrap_or(entry.l_addr);
+        let end = image.iter().map(|m| m.end).max().unwrap_or(start);
+        let name = if entry.l_name.is_empty() {
+            &pathname
+        } else {
+            &entry.l_name
+        };
+
+        so_files.push(SoFileInfo {
+            name: name.rsplit('/').next().unwrap_or(name).to_string(),
+            path: pathname.clone(),
+            start,
+            end,
+            size: end - start,
+            permissions: image
+                .first()
+                .map(|m| m.permissions.clone())
+                .unwrap_or_default(),
+            in_memory: false,
+        });
+    }
+
+    Ok(so_files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testutil::{poll, SpawnedChild};
+    use crate::utils::{default_reader, detect_process_class};
+    use std::process::Command;
+    use std::time::Duration;
+
+    fn is_libc(name: &str) -> bool {
+        name.rsplit('/')
+            .next()
+            .unwrap_or(name)
+            .starts_with("libc.so")
+    }
+
+    #[test]
+    fn link_map_of_spawned_process() {
+        let child = SpawnedChild::spawn(Command::new("sleep").arg("10"));
+        let pid = child.pid();
+        let reader = default_reader(pid);
+        let class = detect_process_class(pid).unwrap();
+
+        // 等待ld.so完成加载
+        let libc = poll(Duration::from_secs(5), || {
+            list_link_map(pid, reader.as_ref(), class)
+                .unwrap_or_default()
+                .into_iter()
+                .find(|so| is_libc(&so.name))
+        })
+        .unwrap();
+        assert!(libc.start != 0 && libc.end > libc.start);
+        assert!(libc.path.starts_with('/'));
+
+        let r_debug = find_r_debug(pid, reader.as_ref()).unwrap();
+        let entry = walk_link_map(reader.as_ref(), class, r_debug)
+            .unwrap()
+            .into_iter()
+            .find(|entry| is_libc(&entry.l_name))
+            .unwrap();
+        assert_ne!(entry.l_addr, 0);
+        assert_ne!(entry.l_ld, 0);
+    }
+}
@@ -0,0 +1,465 @@
+use anyhow::{anyhow, Result};
+use nix::sys::ptrace;
+use nix::sys::signal::{kill, Signal};
+use nix::sys::uio::{process_vm_readv, RemoteIoVec};
+use nix::sys::wait::{waitpid, WaitPidFlag};
+use nix::unistd::Pid;
+use std::fs::{self, File};
+use std::io::{IoSliceMut, Write};
+use std::os::unix::fs::FileExt;
+use std::path::{Path, PathBuf};
+
+use super::elf::PAGE_SIZE;
+use super::process::parse_proc_maps;
+
+/// 无法读取而被填0的内存区间 [start, end)
+pub type MissingRanges = Vec<(u64, u64)>;
+
+/// 目标进程内存读取接口
+pub trait MemoryReader {
+    fn name(&self) -> &'static str;
+
+    fn read_exact(&self, address: u64, buffer: &mut [u8]) -> Result<()>;
+
+    fn read_bytes(&self, address: u64, size: usize) -> Result<Vec<u8>> {
+        let mut buffer = vec![0u8; size];
+        self.read_exact(address, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// 按页读取，无法读取的页填0，同时返回缺失的区间
+    fn read_tolerant(&self, address: u64, size: usize) -> (Vec<u8>, MissingRanges) {
+        let mut buffer = vec![0u8; size];
+        let mut missing = MissingRanges::new();
+        if self.read_exact(address, &mut buffer).is_ok() {
+            return (buffer, missing);
+        }
+
+        let end = address + size as u64;
+        let mut page = address;
+        while page < end {
+            let next = ((page / PAGE_SIZE) + 1) * PAGE_SIZE;
+            let chunk_end = next.min(end);
+            let chunk = &mut buffer[(page - address) as usize..(chunk_end - address) as usize];
+
+            if self.read_exact(page, chunk).is_err() {
+                chunk.fill(0);
+                match missing.last_mut() {
+                    Some(last) if last.1 == page => last.1 = chunk_end,
+                    _ => missing.push((page, chunk_end)),
+                }
+            }
+            page = chunk_end;
+        }
+
+        (buffer, missing)
+    }
+}
+
+/// 在输出文件旁写入缺失区间报告`<output>.missing.txt`
+pub fn write_missing_report(output: &Path, missing: &[(u64, u64)]) -> Result<PathBuf> {
+    let mut report_path = output.as_os_str().to_owned();
+    report_path.push(".missing.txt");
+    let report_path = PathBuf::from(report_path);
+
+    let mut file = File::create(&report_path)?;
+    writeln!(
+        file,
+        "# unreadable ranges zero-filled in {}",
+        output.display()
+    )?;
+    for (start, end) in missing {
+        writeln!(file, "{:#x}-{:#x} {}", start, end, end - start)?;
+    }
+
+    Ok(report_path)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReaderKind {
+    ProcMem,
+    VmReadv,
+    Ptrace,
+    Snapshot,
+}
+
+/// 根据类型创建内存读取后端
+pub fn open_reader(
+    kind: ReaderKind,
+    pid: u32,
+    snapshot: Option<&Path>,
+) -> Result<Box<dyn MemoryReader>> {
+    let reader: Box<dyn MemoryReader> = match kind {
+        ReaderKind::ProcMem => Box::new(ProcMemReader::new(pid)?),
+        ReaderKind::VmReadv => Box::new(VmReadvReader::new(pid)),
+        ReaderKind::Ptrace => Box::new(PtraceReader::new(pid)?),
+        ReaderKind::Snapshot => {
+            let dir = snapshot.ok_or_else(|| anyhow!("Need --snapshot for snapshot reader"))?;
+            Box::new(SnapshotReader::new(dir)?)
+        }
+    };
+    eprintln!("[+] Memory reader: {}", reader.name());
+    Ok(reader)
+}
+
+/// 默认后端：优先/proc/pid/mem，无法打开时使用process_vm_readv
+pub fn default_reader(pid: u32) -> Box<dyn MemoryReader> {
+    match ProcMemReader::new(pid) {
+        Ok(reader) => Box::new(reader),
+        Err(_) => Box::new(VmReadvReader::new(pid)),
+    }
+}
+
+pub struct ProcMemReader {
+    file: File,
+}
+
+impl ProcMemReader {
+    pub fn new(pid: u32) -> Result<Self> {
+        let file = File::open(format!("/proc/{}/mem", pid))
+            .map_err(|e| anyhow!("Failed to open /proc/{}/mem: {}", pid, e))?;
+        Ok(Self { file })
+    }
+}
+
+impl MemoryReader for ProcMemReader {
+    fn name(&self) -> &'static str {
+        "/proc/pid/mem"
+    }
+
+    fn read_exact(&self, address: u64, buffer: &mut [u8]) -> Result<()> {
+        self.file.read_exact_at(buffer, address)?;
+        Ok(())
+    }
+}
+
+pub struct VmReadvReader {
+    pid: Pid,
+}
+
+impl VmReadvReader {
+    pub fn new(pid: u32) -> Self {
+        Self {
+            pid: Pid::from_raw(pid as i32),
+        }
+    }
+}
+
+impl MemoryReader for VmReadvReader {
+    fn name(&self) -> &'static str {
+        "process_vm_readv"
+    }
+
+    fn read_exact(&self, address: u64, buffer: &mut [u8]) -> Result<()> {
+        let mut done = 0;
+        while done < buffer.len() {
+            let remote = [RemoteIoVec {
+                base: address as usize + done,
+                len: buffer.len() - done,
+            }];
+            let read = process_vm_readv(
+                self.pid,
+                &mut [IoSliceMut::new(&mut buffer[done..])],
+                &remote,
+            )?;
+            if read == 0 {
+                return Err(anyhow!(
+                    "process_vm_readv short read at {:#x}",
+                    address + done as u64
+                ));
+            }
+            done += read;
+        }
+        Ok(())
+    }
+}
+
+// 通过PTRACE_PEEKDATA逐字读取，创建时附加主线程，释放时分离
+pub struct PtraceReader {
+    pid: Pid,
+}
+
+impl PtraceReader {
+    pub fn new(pid: u32) -> Result<Self> {
+        let pid = Pid::from_raw(pid as i32);
+        ptrace::attach(pid).map_err(|e| anyhow!("ptrace attach failed: {}", e))?;
+        waitpid(pid, Some(WaitPidFlag::__WALL))?;
+        Ok(Self { pid })
+    }
+}
+
+impl MemoryReader for PtraceReader {
+    fn name(&self) -> &'static str {
+        "ptrace PEEKDATA"
+    }
+
+    fn read_exact(&self, address: u64, buffer: &mut [u8]) -> Result<()> {
+        const WORD: u64 = std::mem::size_of::<usize>() as u64;
+        let start = address & !(WORD - 1);
+        let end = address + buffer.len() as u64;
+
+        let mut word_addr = start;
+        while word_addr < end {
+            let word = ptrace::read(self.pid, word_addr as ptrace::AddressType)
+                .map_err(|e| anyhow!("PEEKDATA failed at {:#x}: {}", word_addr, e))?;
+            for (i, byte) in word.to_le_bytes().iter().enumerate() {
+                let addr = word_addr + i as u64;
+                if addr >= address && addr < end {
+                    buffer[(addr - address) as usize] = *byte;
+                }
+            }
+            word_addr += WORD;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for PtraceReader {
+    fn drop(&mut self) {
+        let _ = ptrace::detach(self.pid, None);
+    }
+}
+
+// 快照目录中除内存段外的文件：maps副本、exe的ELF头和原进程PID
+pub const SNAPSHOT_MAPS: &str = "maps";
+pub const SNAPSHOT_EXE: &str = "exe";
+pub const SNAPSHOT_PID: &str = "pid";
+
+/// 将目标进程所有可读映射写入快照目录，供SnapshotReader离线读取，返回写入的段数
+/// 写入期间暂停目标进程，保证各段内容一致
+pub fn write_snapshot(pid: u32, reader: &dyn MemoryReader, dir: &Path) -> Result<usize> {
+    fs::create_dir_all(dir)?;
+    let target = Pid::from_raw(pid as i32);
+    kill(target, Signal::SIGSTOP)?;
+
+    let result = (|| -> Result<usize> {
+        fs::copy(format!("/proc/{}/maps", pid), dir.join(SNAPSHOT_MAPS))?;
+        let mut exe = [0u8; 0x40];
+        File::open(format!("/proc/{}/exe", pid))?.read_exact_at(&mut exe, 0)?;
+        fs::write(dir.join(SNAPSHOT_EXE), exe)?;
+        fs::write(dir.join(SNAPSHOT_PID), pid.to_string())?;
+
+        let mut written = 0;
+        for mapping in parse_proc_maps(pid)? {
+            // [vvar]等内核映射不可读或每次读取都不同
+            if !mapping.permissions.starts_with('r') || mapping.pathname.starts_with("[v") {
+                continue;
+            }
+            let size = (mapping.end - mapping.start) as usize;
+            let (data, missing) = reader.read_tolerant(mapping.start, size);
+            if missing.iter().map(|(start, end)| end - start).sum::<u64>() == size as u64 {
+                continue;
+            }
+
+            let path = dir.join(format!("{:x}-{:x}.bin", mapping.start, mapping.end));
+            fs::write(&path, data)?;
+            if !missing.is_empty() {
+                write_missing_report(&path, &missing)?;
+            }
+            written += 1;
+        }
+        Ok(written)
+    })();
+
+    kill(target, Signal::SIGCONT)?;
+    result
+}
+
+/// 读取快照中保存的原进程PID，仅用于输出
+pub fn snapshot_pid(dir: &Path) -> u32 {
+    fs::read_to_string(dir.join(SNAPSHOT_PID))
+        .ok()
+        .and_then(|pid| pid.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+struct SnapshotRegion {
+    start: u64,
+    end: u64,
+    path: PathBuf,
+}
+
+/// 离线快照，目录中每个文件对应一段内存，文件名为`<start>-<end>.bin`（十六进制）
+pub struct SnapshotReader {
+    regions: Vec<SnapshotRegion>,
+}
+
+impl SnapshotReader {
+    pub fn new(dir: &Path) -> Result<Self> {
+        let mut regions = Vec::new();
+
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let stem = match path.file_stem() {
+                Some(stem) => stem.to_string_lossy().into_owned(),
+                None => continue,
+            };
+            let range = stem.rsplit('_').next().unwrap_or(&stem);
+            if let Some((start, end)) = range.split_once('-') {
+                if let (Ok(start), Ok(end)) = (
+                    u64::from_str_radix(start.trim_start_matches("0x"), 16),
+                    u64::from_str_radix(end.trim_start_matches("0x"), 16),
+                ) {
+                    regions.push(SnapshotRegion { start, end, path });
+                }
+            }
+        }
+
+        if regions.is_empty() {
+            return Err(anyhow!("No snapshot regions found in {}", dir.display()));
+        }
+        regions.sort_by_key(|r| r.start);
+        eprintln!(
+            "[+] Loaded {} snapshot regions from {}",
+            regions.len(),
+            dir.display()
+        );
+
+        Ok(Self { regions })
+    }
+}
+
+impl MemoryReader for SnapshotReader {
+    fn name(&self) -> &'static str {
+        "offline snapshot"
+    }
+
+    fn read_exact(&self, address: u64, buffer: &mut [u8]) -> Result<()> {
+        let mut done = 0;
+        while done < buffer.len() {
+            let addr = address + done as u64;
+            let region = self
+                .regions
+                .iter()
+                .find(|r| addr >= r.start && addr < r.end)
+                .ok_or_else(|| anyhow!("Address {:#x} not in snapshot", addr))?;
+
+            let len = ((region.end - addr) as usize).min(buffer.len() - done);
+            File::open(&region.path)?
+                .read_exact_at(&mut buffer[done..done + len], addr - region.start)?;
+            done += len;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::process::read_maps;
+    use crate::utils::testutil::{poll, SpawnedChild, TempDir};
+    use std::process::Command;
+    use std::time::Duration;
+
+    fn pattern(seed: u8, size: usize) -> Vec<u8> {
+        (0..size).map(|i| seed.wrapping_add(i as u8)).collect()
+    }
+
+    // 等待子进程完成exec，返回可执行文件首个映射的地址和文件头
+    fn exe_header(pid: u32) -> (u64, Vec<u8>) {
+        let (exe, base) = poll(Duration::from_secs(5), || {
+            let exe = fs::read_link(format!("/proc/{}/exe", pid)).ok()?;
+            let mapping = parse_proc_maps(pid)
+                .ok()?
+                .into_iter()
+                .find(|m| m.offset == 0 && Path::new(&m.pathname) == exe)?;
+            Some((exe, mapping.start))
+        })
+        .unwrap();
+        (base, fs::read(exe).unwrap()[..0x40].to_vec())
+    }
+
+    #[test]
+    fn live_readers_read_exe_header() {
+        let child = SpawnedChild::spawn(Command::new("sleep").arg("10"));
+        let pid = child.pid();
+        let (base, header) = exe_header(pid);
+
+        let readers: Vec<Box<dyn MemoryReader>> = vec![
+            Box::new(ProcMemReader::new(pid).unwrap()),
+            Box::new(VmReadvReader::new(pid)),
+            Box::new(PtraceReader::new(pid).unwrap()),
+        ];
+        for reader in &readers {
+            assert_eq!(
+                reader.read_bytes(base, 0x40).unwrap(),
+                header,
+                "{}",
+                reader.name()
+            );
+        }
+    }
+
+    #[test]
+    fn snapshot_reader_across_gap_and_short_file() {
+        let dir = TempDir::new("snapshot_gap");
+        let first = pattern(1, 0x1000);
+        let second = pattern(2, 0x1000);
+        fs::write(dir.path().join("10000-11000.bin"), &first).unwrap();
+        fs::write(dir.path().join("11000-12000.bin"), &second).unwrap();
+        // 文件比名字声明的区间短
+        fs::write(dir.path().join("13000-14000.bin"), pattern(3, 0x800)).unwrap();
+        let reader = SnapshotReader::new(dir.path()).unwrap();
+
+        // 跨两个相邻文件
+        let data = reader.read_bytes(0x10ff0, 0x20).unwrap();
+        assert_eq!(data[..0x10], first[0xff0..]);
+        assert_eq!(data[0x10..], second[..0x10]);
+
+        assert!(reader.read_bytes(0x11ff0, 0x20).is_err());
+        assert!(reader.read_bytes(0x13700, 0x200).is_err());
+
+        let (data, missing) = reader.read_tolerant(0x11000, 0x3000);
+        assert_eq!(data[..0x1000], second[..]);
+        assert!(data[0x1000..].iter().all(|&b| b == 0));
+        assert_eq!(missing, vec![(0x12000, 0x14000)]);
+    }
+
+    #[test]
+    fn read_tolerant_zero_fills_hole() {
+        let dir = TempDir::new("snapshot_hole");
+        let first = pattern(1, 0x1000);
+        let last = pattern(3, 0x1000);
+        fs::write(dir.path().join("20000-21000.bin"), &first).unwrap();
+        fs::write(dir.path().join("22000-23000.bin"), &last).unwrap();
+        let reader = SnapshotReader::new(dir.path()).unwrap();
+
+        let (data, missing) = reader.read_tolerant(0x20800, 0x2000);
+        assert_eq!(data.len(), 0x2000);
+        assert_eq!(data[..0x800], first[0x800..]);
+        assert!(data[0x800..0x1800].iter().all(|&b| b == 0));
+        assert_eq!(data[0x1800..], last[..0x800]);
+        assert_eq!(missing, vec![(0x21000, 0x22000)]);
+
+        let output = dir.path().join("dump.so");
+        let report = write_missing_report(&output, &missing).unwrap();
+        assert_eq!(report, dir.path().join("dump.so.missing.txt"));
+        assert_eq!(
+            fs::read_to_string(report).unwrap(),
+            format!(
+                "# unreadable ranges zero-filled in {}\n0x21000-0x22000 4096\n",
+                output.display()
+            )
+        );
+    }
+
+    #[test]
+    fn write_snapshot_roundtrip() {
+        let child = SpawnedChild::spawn(Command::new("sleep").arg("10"));
+        let pid = child.pid();
+        let (base, header) = exe_header(pid);
+        let dir = TempDir::new("snapshot_roundtrip");
+
+        let written = write_snapshot(pid, &ProcMemReader::new(pid).unwrap(), dir.path()).unwrap();
+        assert!(written > 0);
+        assert_eq!(snapshot_pid(dir.path()), pid);
+        assert_eq!(fs::read(dir.path().join(SNAPSHOT_EXE)).unwrap(), header);
+        assert!(read_maps(&dir.path().join(SNAPSHOT_MAPS))
+            .unwrap()
+            .iter()
+            .any(|m| m.start == base));
+
+        let reader = SnapshotReader::new(dir.path()).unwrap();
+        assert_eq!(reader.read_bytes(base, 0x40).unwrap(), header);
+    }
+}
@@ -1,6 +1,21 @@
+pub mod elf;
+pub mod json;
+pub mod layout;
+pub mod linkmap;
+pub mod memory;
 pub mod process;
+pub mod reloc;
+#[cfg(test)]
+pub mod testutil;
+pub mod tracer;
 pub mod types;
 
+pub use elf::*;
+pub use json::*;
+pub use layout::*;
+pub use linkmap::*;
+pub use memory::*;
 pub use process::*;
+pub use reloc::*;
+pub use tracer::*;
 pub use types::*;
-pub use process::SoFileInfo;
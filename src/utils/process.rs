@@ -1,5 +1,15 @@
 use anyhow::{anyhow, Result};
 use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+use super::elf::{
+    is_plausible_image, load_span, parse_program_headers, ElfClass, ElfHeader, ELF_MAGIC,
+    PAGE_SIZE,
+};
+use super::memory::MemoryReader;
+use super::types::MemoryMapping;
 
 pub fn get_pid_by_name(process_name: &str) -> Result<u32> {
     let proc_dir = std::fs::read_dir("/proc")?;
@@ -22,55 +32,223 @@ pub fn get_pid_by_name(process_name: &str) -> Result<u32> {
     Err(anyhow!("Process {} not found", process_name))
 }
 
-/// 列举指定PID的所有SO文件
-pub fn list_so_files(pid: u32) -> Result<Vec<SoFileInfo>> {
+/// 读取目标进程exe的ELF头
+pub fn read_exe_header(pid: u32) -> Result<ElfHeader> {
+    read_elf_header_file(Path::new(&format!("/proc/{}/exe", pid)))
+}
+
+/// 读取文件开头的ELF头，快照中的exe只保存了这一部分
+pub fn read_elf_header_file(path: &Path) -> Result<ElfHeader> {
+    let mut header = [0u8; 0x40];
+    let mut exe = std::fs::File::open(path)?;
+    exe.read_exact(&mut header)?;
+    ElfHeader::parse(&header)
+}
+
+/// 检测目标进程的位数，优先读取exe的ELF头，失败时根据映射的linker判断
+pub fn detect_process_class(pid: u32) -> Result<ElfClass> {
+    if let Ok(header) = read_exe_header(pid) {
+        return Ok(header.class);
+    }
+
     let maps_path = format!("/proc/{}/maps", pid);
     let content = std::fs::read_to_string(&maps_path)
         .map_err(|_| anyhow!("Failed to read /proc/{}/maps", pid))?;
 
-    let mut so_files: HashMap<String, SoFileInfo> = HashMap::new();
-
-    for line in content.lines() {
-        if line.contains(".so") {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 6 {
-                let addr_range: Vec<&str> = parts[0].split('-').collect();
-                if addr_range.len() == 2 {
-                    if let (Ok(start), Ok(end)) = (
-                        u64::from_str_radix(addr_range[0], 16),
-                        u64::from_str_radix(addr_range[1], 16),
-                    ) {
-                        let permissions = parts[1].to_string();
-                        let pathname = parts[5..].join(" ");
-
-                        if !pathname.is_empty() && pathname.contains(".so") {
-                            let so_name = pathname.split('/').last().unwrap_or(&pathname).to_string();
-                            
-                            if let Some(existing) = so_files.get_mut(&so_name) {
-                                // 如果已存在同名SO，更新地址范围
-                                existing.start = existing.start.min(start);
-                                existing.end = existing.end.max(end);
-                                existing.size = existing.end - existing.start;
-                            } else {
-                                so_files.insert(so_name.clone(), SoFileInfo {
-                                    name: so_name,
-                                    path: pathname,
-                                    start,
-                                    end,
-                                    size: end - start,
-                                    permissions,
-                                });
-                            }
-                        }
-                    }
+    if content.lines().any(|line| line.ends_with("/linker64")) {
+        Ok(ElfClass::Elf64)
+    } else if content.lines().any(|line| line.ends_with("/linker")) {
+        Ok(ElfClass::Elf32)
+    } else {
+        Err(anyhow!("Could not detect bitness of process {}", pid))
+    }
+}
+
+/// 解析/proc/pid/maps
+pub fn parse_proc_maps(pid: u32) -> Result<Vec<MemoryMapping>> {
+    read_maps(Path::new(&format!("/proc/{}/maps", pid)))
+}
+
+/// 解析maps格式的文件，离线快照中保存了一份目标进程的maps
+pub fn read_maps(path: &Path) -> Result<Vec<MemoryMapping>> {
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+    let mut mappings = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        let parts: Vec<&str> = line.split_whitespace().collect();
+
+        if parts.len() >= 5 {
+            let addr_range: Vec<&str> = parts[0].split('-').collect();
+            let start = u64::from_str_radix(addr_range[0], 16)?;
+            let end = u64::from_str_radix(addr_range[1], 16)?;
+            let permissions = parts[1].to_string();
+            let offset = u64::from_str_radix(parts[2], 16)?;
+            let device = parts[3].to_string();
+            let inode = parts[4].parse::<u64>()?;
+            let pathname = parts[5..].join(" ");
+
+            mappings.push(MemoryMapping {
+                start,
+                end,
+                permissions,
+                offset,
+                device,
+                inode,
+                pathname,
+            });
+        }
+    }
+
+    Ok(mappings)
+}
+
+/// 列举指定PID的所有SO文件
+pub fn list_so_files(pid: u32) -> Result<Vec<SoFileInfo>> {
+    let mappings =
+        parse_proc_maps(pid).map_err(|_| anyhow!("Failed to read /proc/{}/maps", pid))?;
+    Ok(so_files_in(&mappings))
+}
+
+/// 按完整路径合并映射中的SO文件，不同目录下的同名SO是不同的库
+pub fn so_files_in(mappings: &[MemoryMapping]) -> Vec<SoFileInfo> {
+    let mut so_files: HashMap<&str, SoFileInfo> = HashMap::new();
+
+    for mapping in mappings {
+        let pathname = mapping.pathname.as_str();
+        if !pathname.contains(".so") {
+            continue;
+        }
+
+        if let Some(existing) = so_files.get_mut(pathname) {
+            // 如果已存在同一SO，更新地址范围
+            existing.start = existing.start.min(mapping.start);
+            existing.end = existing.end.max(mapping.end);
+            existing.size = existing.end - existing.start;
+        } else {
+            so_files.insert(
+                pathname,
+                SoFileInfo {
+                    name: pathname.rsplit('/').next().unwrap_or(pathname).to_string(),
+                    path: pathname.to_string(),
+                    start: mapping.start,
+                    end: mapping.end,
+                    size: mapping.end - mapping.start,
+                    permissions: mapping.permissions.clone(),
+                    in_memory: false,
+                },
+            );
+        }
+    }
+
+    let mut result: Vec<SoFileInfo> = so_files.into_values().collect();
+    result.sort_by_key(|so| so.start);
+    result
+}
+
+// 匿名、memfd、ashmem或已删除文件的映射，解密后的SO常被放在这里
+fn is_anonymous_mapping(pathname: &str) -> bool {
+    pathname.is_empty()
+        || pathname.starts_with("[anon")
+        || pathname.starts_with("/dev/ashmem")
+        || pathname.contains("memfd:")
+        || pathname.ends_with("(deleted)")
+}
+
+/// 扫描匿名映射中按页对齐的ELF头，找出只存在于内存中的SO
+pub fn scan_memory_elfs(
+    mappings: &[MemoryMapping],
+    reader: &dyn MemoryReader,
+) -> Result<Vec<SoFileInfo>> {
+    const CHUNK_SIZE: u64 = 0x100000;
+    const MAX_SCAN_SIZE: u64 = 0x10000000;
+
+    let mut images = Vec::new();
+
+    for mapping in mappings {
+        if !mapping.permissions.starts_with('r')
+            || !is_anonymous_mapping(&mapping.pathname)
+            || mapping.end - mapping.start > MAX_SCAN_SIZE
+        {
+            continue;
+        }
+
+        let mut chunk_start = mapping.start;
+        while chunk_start < mapping.end {
+            let chunk_size = CHUNK_SIZE.min(mapping.end - chunk_start);
+            let (chunk, _) = reader.read_tolerant(chunk_start, chunk_size as usize);
+
+            for page in (0..chunk.len()).step_by(PAGE_SIZE as usize) {
+                if !chunk[page..].starts_with(ELF_MAGIC) {
+                    continue;
                 }
+
+                let start = chunk_start + page as u64;
+                let Some(size) = probe_elf_image(reader, start) else {
+                    continue;
+                };
+                let label = if mapping.pathname.is_empty() {
+                    "[anon]"
+                } else {
+                    mapping.pathname.rsplit('/').next().unwrap_or(&mapping.pathname)
+                };
+
+                images.push(SoFileInfo {
+                    name: format!("{}@{:#x}", label, start),
+                    path: mapping.pathname.clone(),
+                    start,
+                    end: start + size,
+                    size,
+                    permissions: mapping.permissions.clone(),
+                    in_memory: true,
+                });
             }
+            chunk_start += chunk_size;
         }
     }
 
-    let mut result: Vec<SoFileInfo> = so_files.into_values().collect();
-    result.sort_by(|a, b| a.start.cmp(&b.start));
-    Ok(result)
+    Ok(images)
+}
+
+/// 列举映射中所有ELF镜像：文件映射起始处的ELF头以及匿名内存中的ELF
+pub fn list_elf_images(
+    mappings: &[MemoryMapping],
+    reader: &dyn MemoryReader,
+) -> Result<Vec<SoFileInfo>> {
+    let mut images = Vec::new();
+
+    for mapping in mappings {
+        if !mapping.permissions.starts_with('r') || is_anonymous_mapping(&mapping.pathname) {
+            continue;
+        }
+        let Some(size) = probe_elf_image(reader, mapping.start) else {
+            continue;
+        };
+
+        images.push(SoFileInfo {
+            name: mapping.pathname.rsplit('/').next().unwrap_or(&mapping.pathname).to_string(),
+            path: mapping.pathname.clone(),
+            start: mapping.start,
+            end: mapping.start + size,
+            size,
+            permissions: mapping.permissions.clone(),
+            in_memory: false,
+        });
+    }
+
+    images.extend(scan_memory_elfs(mappings, reader)?);
+    images.sort_by_key(|image| image.start);
+    Ok(images)
+}
+
+/// 校验ELF头和程序头是否合理，返回PT_LOAD覆盖的大小
+pub fn probe_elf_image(reader: &dyn MemoryReader, start: u64) -> Option<u64> {
+    let data = reader.read_bytes(start, PAGE_SIZE as usize).ok()?;
+    let header = ElfHeader::parse(&data).ok()?;
+    let phdrs = parse_program_headers(&data, &header).ok()?;
+
+    is_plausible_image(&header, &phdrs).then(|| load_span(&phdrs))
 }
 
 /// SO文件信息结构体
@@ -82,4 +260,14 @@ pub struct SoFileInfo {
     pub end: u64,
     pub size: u64,
     pub permissions: String,
+    pub in_memory: bool,
+}
+
+impl SoFileInfo {
+    /// 是否为系统库（/system、/apex、/vendor）
+    pub fn is_system_lib(&self) -> bool {
+        ["/system/", "/apex/", "/vendor/"]
+            .iter()
+            .any(|prefix| self.path.starts_with(prefix))
+    }
 }
@@ -0,0 +1,554 @@
+use anyhow::{anyhow, Result};
+use goblin::elf::dynamic::{
+    DT_JMPREL, DT_PLTREL, DT_PLTRELSZ, DT_REL, DT_RELA, DT_RELASZ, DT_RELSZ,
+};
+use goblin::elf::header::{EM_386, EM_AARCH64, EM_ARM, EM_X86_64};
+use goblin::elf::reloc::{
+    R_386_GLOB_DAT, R_386_JMP_SLOT, R_386_RELATIVE, R_AARCH64_GLOB_DAT, R_AARCH64_JUMP_SLOT,
+    R_AARCH64_RELATIVE, R_ARM_GLOB_DAT, R_ARM_JUMP_SLOT, R_ARM_RELATIVE, R_X86_64_GLOB_DAT,
+    R_X86_64_JUMP_SLOT, R_X86_64_RELATIVE,
+};
+
+use super::elf::{Dyn, ElfClass};
+
+// goblin中没有的动态段标签
+pub const DT_RELRSZ: u64 = 35;
+pub const DT_RELR: u64 = 36;
+pub const DT_RELRENT: u64 = 37;
+pub const DT_ANDROID_REL: u64 = 0x6000_000f;
+pub const DT_ANDROID_RELSZ: u64 = 0x6000_0010;
+pub const DT_ANDROID_RELA: u64 = 0x6000_0011;
+pub const DT_ANDROID_RELASZ: u64 = 0x6000_0012;
+pub const DT_ANDROID_RELR: u64 = 0x6fff_e000;
+pub const DT_ANDROID_RELRSZ: u64 = 0x6fff_e001;
+pub const DT_ANDROID_RELRENT: u64 = 0x6fff_e003;
+
+const APS2_MAGIC: &[u8] = b"APS2";
+const RELOCATION_GROUPED_BY_INFO_FLAG: u64 = 1;
+const RELOCATION_GROUPED_BY_OFFSET_DELTA_FLAG: u64 = 2;
+const RELOCATION_GROUPED_BY_ADDEND_FLAG: u64 = 4;
+const RELOCATION_GROUP_HAS_ADDEND_FLAG: u64 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelocFormat {
+    Rel,
+    Rela,
+    AndroidRel,
+    AndroidRela,
+    Relr,
+}
+
+/// 解码后的重定位项，RELR项的类型为对应架构的RELATIVE
+#[derive(Debug, Clone, Copy)]
+pub struct Relocation {
+    pub format: RelocFormat,
+    pub r_offset: u64,
+    pub r_type: u32,
+    pub r_sym: u32,
+    pub r_addend: i64,
+}
+
+/// 各架构R_*_RELATIVE的类型值
+pub fn relative_type(machine: u16) -> Option<u32> {
+    match machine {
+        EM_AARCH64 => Some(R_AARCH64_RELATIVE),
+        EM_X86_64 => Some(R_X86_64_RELATIVE),
+        EM_ARM => Some(R_ARM_RELATIVE),
+        EM_386 => Some(R_386_RELATIVE),
+        _ => None,
+    }
+}
+
+/// 是否为写入GOT的GLOB_DAT/JUMP_SLOT类型
+pub fn is_got_type(machine: u16, r_type: u32) -> bool {
+    match machine {
+        EM_AARCH64 => matches!(r_type, R_AARCH64_GLOB_DAT | R_AARCH64_JUMP_SLOT),
+        EM_X86_64 => matches!(r_type, R_X86_64_GLOB_DAT | R_X86_64_JUMP_SLOT),
+        EM_ARM => matches!(r_type, R_ARM_GLOB_DAT | R_ARM_JUMP_SLOT),
+        EM_386 => matches!(r_type, R_386_GLOB_DAT | R_386_JMP_SLOT),
+        _ => false,
+    }
+}
+
+fn split_info(class: ElfClass, r_info: u64) -> (u32, u32) {
+    match class {
+        ElfClass::Elf32 => ((r_info & 0xff) as u32, (r_info as u32) >> 8),
+        ElfClass::Elf64 => (r_info as u32, (r_info >> 32) as u32),
+    }
+}
+
+/// 解析标准的REL/RELA表
+pub fn parse_rel_table(data: &[u8], class: ElfClass, is_rela: bool) -> Vec<Relocation> {
+    let ptr_size = class.ptr_size();
+    let entsize = if is_rela { ptr_size * 3 } else { ptr_size * 2 };
+
+    data.chunks_exact(entsize)
+        .filter_map(|entry| {
+            let r_offset = class.read_word(entry, 0)?;
+            let (r_type, r_sym) = split_info(class, class.read_word(entry, ptr_size)?);
+            let r_addend = if is_rela {
+                sign_extend(class, class.read_word(entry, ptr_size * 2)?)
+            } else {
+                0
+            };
+            Some(Relocation {
+                format: if is_rela {
+                    RelocFormat::Rela
+                } else {
+                    RelocFormat::Rel
+                },
+                r_offset,
+                r_type,
+                r_sym,
+                r_addend,
+            })
+        })
+        .collect()
+}
+
+fn sign_extend(class: ElfClass, value: u64) -> i64 {
+    match class {
+        ElfClass::Elf32 => value as u32 as i32 as i64,
+        ElfClass::Elf64 => value as i64,
+    }
+}
+
+struct Sleb128Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl Sleb128Reader<'_> {
+    fn next(&mut self) -> Result<i64> {
+        let mut value: i64 = 0;
+        let mut shift = 0;
+        loop {
+            let byte = *self
+                .data
+                .get(self.offset)
+                .ok_or_else(|| anyhow!("Truncated SLEB128 at {:#x}", self.offset))?;
+            self.offset += 1;
+            if shift < 64 {
+                value |= ((byte & 0x7f) as i64) << shift;
+            }
+            shift += 7;
+            if byte & 0x80 == 0 {
+                if shift < 64 && byte & 0x40 != 0 {
+                    value |= -1i64 << shift;
+                }
+                return Ok(value);
+            }
+        }
+    }
+}
+
+/// 解码bionic的APS2压缩重定位（DT_ANDROID_REL/DT_ANDROID_RELA）
+pub fn parse_android_relocs(
+    data: &[u8],
+    class: ElfClass,
+    is_rela: bool,
+) -> Result<Vec<Relocation>> {
+    if !data.starts_with(APS2_MAGIC) {
+        return Err(anyhow!("Missing APS2 magic in packed relocations"));
+    }
+    let mask = match class {
+        ElfClass::Elf32 => u32::MAX as u64,
+        ElfClass::Elf64 => u64::MAX,
+    };
+    let format = if is_rela {
+        RelocFormat::AndroidRela
+    } else {
+        RelocFormat::AndroidRel
+    };
+
+    let mut reader = Sleb128Reader {
+        data,
+        offset: APS2_MAGIC.len(),
+    };
+    let count = reader.next()? as usize;
+    let mut r_offset = reader.next()? as u64;
+    let mut r_info = 0u64;
+    let mut r_addend = 0i64;
+    let mut relocs = Vec::with_capacity(count.min(data.len()));
+
+    while relocs.len() < count {
+        let group_size = reader.next()? as usize;
+        let group_flags = reader.next()? as u64;
+        let by_offset_delta = group_flags & RELOCATION_GROUPED_BY_OFFSET_DELTA_FLAG != 0;
+        let by_info = group_flags & RELOCATION_GROUPED_BY_INFO_FLAG != 0;
+        let by_addend = group_flags & RELOCATION_GROUPED_BY_ADDEND_FLAG != 0;
+        let has_addend = group_flags & RELOCATION_GROUP_HAS_ADDEND_FLAG != 0;
+
+        let group_offset_delta = if by_offset_delta {
+            reader.next()? as u64
+        } else {
+            0
+        };
+        if by_info {
+            r_info = reader.next()? as u64;
+        }
+        if has_addend && by_addend {
+            r_addend = r_addend.wrapping_add(reader.next()?);
+        } else if !has_addend {
+            r_addend = 0;
+        }
+
+        for _ in 0..group_size.min(count - relocs.len()) {
+            let delta = if by_offset_delta {
+                group_offset_delta
+            } else {
+                reader.next()? as u64
+            };
+            r_offset = r_offset.wrapping_add(delta) & mask;
+            if !by_info {
+                r_info = reader.next()? as u64;
+            }
+            if has_addend && !by_addend {
+                r_addend = r_addend.wrapping_add(reader.next()?);
+            }
+
+            let (r_type, r_sym) = split_info(class, r_info & mask);
+            relocs.push(Relocation {
+                format,
+                r_offset,
+                r_type,
+                r_sym,
+                r_addend: sign_extend(class, r_addend as u64 & mask),
+            });
+        }
+    }
+
+    Ok(relocs)
+}
+
+/// 解码RELR表，偶数项为地址，奇数项为其后若干字的位图
+pub fn parse_relr(data: &[u8], class: ElfClass) -> Vec<u64> {
+    let word = class.ptr_size() as u64;
+    let bits = word * 8;
+    let mut offsets = Vec::new();
+    let mut base = 0u64;
+
+    for chunk in data.chunks_exact(word as usize) {
+        let Some(entry) = class.read_word(chunk, 0) else {
+            break;
+        };
+        // 地址溢出说明表已损坏，停止解码
+        let next = if entry & 1 == 0 {
+            offsets.push(entry);
+            entry.checked_add(word)
+        } else {
+            for i in 0..bits - 1 {
+                if (entry >> (i + 1)) & 1 != 0 {
+                    let Some(offset) = base.checked_add(i * word) else {
+                        return offsets;
+                    };
+                    offsets.push(offset);
+                }
+            }
+            base.checked_add((bits - 1) * word)
+        };
+        match next {
+            Some(next) => base = next,
+            None => break,
+        }
+    }
+
+    offsets
+}
+
+/// 编码为标准的REL/RELA表
+pub fn encode_rel_table(relocs: &[Relocation], class: ElfClass, is_rela: bool) -> Vec<u8> {
+    let ptr_size = class.ptr_size();
+    let entsize = if is_rela { ptr_size * 3 } else { ptr_size * 2 };
+    let mut table = vec![0u8; relocs.len() * entsize];
+
+    for (i, reloc) in relocs.iter().enumerate() {
+        let r_info = match class {
+            ElfClass::Elf32 => ((reloc.r_sym as u64) << 8) | (reloc.r_type as u64 & 0xff),
+            ElfClass::Elf64 => ((reloc.r_sym as u64) << 32) | reloc.r_type as u64,
+        };
+        let offset = i * entsize;
+        class.write_word(&mut table, offset, reloc.r_offset);
+        class.write_word(&mut table, offset + ptr_size, r_info);
+        if is_rela {
+            class.write_word(&mut table, offset + ptr_size * 2, reloc.r_addend as u64);
+        }
+    }
+
+    table
+}
+
+/// 根据动态段解码镜像中的所有重定位表，dynamic中的地址须为虚拟地址
+pub fn decode_relocations(
+    image: &[u8],
+    class: ElfClass,
+    machine: u16,
+    min_vaddr: u64,
+    dynamic: &[Dyn],
+) -> Result<Vec<Relocation>> {
+    let dyn_val = |tag: u64| dynamic.iter().find(|d| d.d_tag == tag).map(|d| d.d_val);
+    let table = |addr_tag: u64, size_tag: u64| -> Option<&[u8]> {
+        let start = dyn_val(addr_tag)?.checked_sub(min_vaddr)? as usize;
+        let size = dyn_val(size_tag)? as usize;
+        image.get(start..start.checked_add(size)?)
+    };
+
+    let mut relocs = Vec::new();
+    if let Some(data) = table(DT_RELA, DT_RELASZ) {
+        relocs.extend(parse_rel_table(data, class, true));
+    }
+    if let Some(data) = table(DT_REL, DT_RELSZ) {
+        relocs.extend(parse_rel_table(data, class, false));
+    }
+    if let Some(data) = table(DT_JMPREL, DT_PLTRELSZ) {
+        relocs.extend(parse_rel_table(
+            data,
+            class,
+            dyn_val(DT_PLTREL) == Some(DT_RELA),
+        ));
+    }
+    if let Some(data) = table(DT_ANDROID_RELA, DT_ANDROID_RELASZ) {
+        relocs.extend(parse_android_relocs(data, class, true)?);
+    }
+    if let Some(data) = table(DT_ANDROID_REL, DT_ANDROID_RELSZ) {
+        relocs.extend(parse_android_relocs(data, class, false)?);
+    }
+
+    let relr = table(DT_RELR, DT_RELRSZ).or_else(|| table(DT_ANDROID_RELR, DT_ANDROID_RELRSZ));
+    if let Some(data) = relr {
+        let r_type = relative_type(machine)
+            .ok_or_else(|| anyhow!("Unsupported machine {} for RELR", machine))?;
+        relocs.extend(
+            parse_relr(data, class)
+                .into_iter()
+                .map(|r_offset| Relocation {
+                    format: RelocFormat::Relr,
+                    r_offset,
+                    r_type,
+                    r_sym: 0,
+                    r_addend: 0,
+                }),
+        );
+    }
+
+    Ok(relocs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use goblin::elf::reloc::R_386_32;
+
+    fn sleb128(mut value: i64, out: &mut Vec<u8>) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            let done = (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0);
+            out.push(if done { byte } else { byte | 0x80 });
+            if done {
+                break;
+            }
+        }
+    }
+
+    fn aps2(values: &[i64]) -> Vec<u8> {
+        let mut data = APS2_MAGIC.to_vec();
+        for value in values {
+            sleb128(*value, &mut data);
+        }
+        data
+    }
+
+    #[test]
+    fn sleb128_values() {
+        let mut data = Vec::new();
+        for value in [0, 63, 64, -1, -64, -65, 0x1234_5678, i64::MIN] {
+            sleb128(value, &mut data);
+        }
+        let mut reader = Sleb128Reader {
+            data: &data,
+            offset: 0,
+        };
+
+        for expected in [0, 63, 64, -1, -64, -65, 0x1234_5678, i64::MIN] {
+            assert_eq!(reader.next().unwrap(), expected);
+        }
+        assert!(reader.next().is_err());
+    }
+
+    #[test]
+    fn rel_table_roundtrip() {
+        for (class, is_rela) in [(ElfClass::Elf32, false), (ElfClass::Elf64, true)] {
+            let relocs = [
+                Relocation {
+                    format: RelocFormat::Rel,
+                    r_offset: 0x2000,
+                    r_type: 8,
+                    r_sym: 0,
+                    r_addend: if is_rela { -0x10 } else { 0 },
+                },
+                Relocation {
+                    format: RelocFormat::Rel,
+                    r_offset: 0x2008,
+                    r_type: 6,
+                    r_sym: 3,
+                    r_addend: 0,
+                },
+            ];
+            let table = encode_rel_table(&relocs, class, is_rela);
+            let parsed = parse_rel_table(&table, class, is_rela);
+
+            assert_eq!(table.len(), class.ptr_size() * if is_rela { 6 } else { 4 });
+            assert_eq!(parsed.len(), 2);
+            assert_eq!(parsed[0].r_addend, relocs[0].r_addend);
+            assert_eq!((parsed[1].r_offset, parsed[1].r_type), (0x2008, 6));
+            assert_eq!(parsed[1].r_sym, 3);
+        }
+    }
+
+    #[test]
+    fn android_relocs_groups() {
+        let grouped = (RELOCATION_GROUPED_BY_INFO_FLAG
+            | RELOCATION_GROUPED_BY_OFFSET_DELTA_FLAG
+            | RELOCATION_GROUP_HAS_ADDEND_FLAG) as i64;
+        let glob_dat = (5i64 << 32) | R_AARCH64_GLOB_DAT as i64;
+        let data = aps2(&[
+            // 3项，起始偏移0x1000
+            3,
+            0x1000,
+            // 第1组：相同类型和偏移增量，每项单独的addend增量
+            2,
+            grouped,
+            8,
+            R_AARCH64_RELATIVE as i64,
+            0x100,
+            -0x40,
+            // 第2组：无addend
+            1,
+            0,
+            0x20,
+            glob_dat,
+        ]);
+        let relocs = parse_android_relocs(&data, ElfClass::Elf64, true).unwrap();
+        let decoded: Vec<_> = relocs
+            .iter()
+            .map(|r| (r.r_offset, r.r_type, r.r_sym, r.r_addend))
+            .collect();
+
+        assert_eq!(
+            decoded,
+            [
+                (0x1008, R_AARCH64_RELATIVE, 0, 0x100),
+                (0x1010, R_AARCH64_RELATIVE, 0, 0xc0),
+                (0x1030, R_AARCH64_GLOB_DAT, 5, 0),
+            ]
+        );
+        assert!(relocs.iter().all(|r| r.format == RelocFormat::AndroidRela));
+    }
+
+    #[test]
+    fn android_relocs_32bit_rel() {
+        let info = (2i64 << 8) | R_386_32 as i64;
+        let data = aps2(&[
+            1,
+            0x4000,
+            1,
+            RELOCATION_GROUPED_BY_INFO_FLAG as i64,
+            info,
+            -4,
+        ]);
+        let relocs = parse_android_relocs(&data, ElfClass::Elf32, false).unwrap();
+
+        assert_eq!(relocs.len(), 1);
+        assert_eq!(relocs[0].r_offset, 0x3ffc);
+        assert_eq!((relocs[0].r_type, relocs[0].r_sym), (R_386_32, 2));
+    }
+
+    #[test]
+    fn android_relocs_invalid() {
+        assert!(parse_android_relocs(b"APS1\x01", ElfClass::Elf64, true).is_err());
+        // 声明了2项但数据在第1组中截断
+        assert!(parse_android_relocs(&aps2(&[2, 0, 2, 0, 8]), ElfClass::Elf64, true).is_err());
+    }
+
+    #[test]
+    fn relr_bitmaps() {
+        let class = ElfClass::Elf64;
+        let mut data = vec![0u8; 24];
+        class.write_word(&mut data, 0, 0x1000).unwrap();
+        class.write_word(&mut data, 8, 1 | 1 << 1 | 1 << 3).unwrap();
+        class.write_word(&mut data, 16, 1 | 1 << 1).unwrap();
+
+        assert_eq!(parse_relr(&data, class), [0x1000, 0x1008, 0x1018, 0x1200]);
+
+        let class = ElfClass::Elf32;
+        let mut data = vec![0u8; 12];
+        class.write_word(&mut data, 0, 0x100).unwrap();
+        class.write_word(&mut data, 4, 1 | 1 << 31).unwrap();
+        class.write_word(&mut data, 8, 0x400).unwrap();
+
+        assert_eq!(parse_relr(&data, class), [0x100, 0x104 + 30 * 4, 0x400]);
+    }
+
+    #[test]
+    fn relr_stops_on_overflow() {
+        let class = ElfClass::Elf64;
+        let mut data = vec![0u8; 24];
+        class.write_word(&mut data, 0, u64::MAX - 7).unwrap();
+        class.write_word(&mut data, 8, 1 | 1 << 1).unwrap();
+        class.write_word(&mut data, 16, 0x1000).unwrap();
+
+        assert_eq!(parse_relr(&data, class), [u64::MAX - 7]);
+
+        class.write_word(&mut data, 0, u64::MAX - 0xff).unwrap();
+        class
+            .write_word(&mut data, 8, 1 | 1 << 1 | 1 << 63)
+            .unwrap();
+
+        assert_eq!(parse_relr(&data, class), [u64::MAX - 0xff, u64::MAX - 0xf7]);
+    }
+
+    #[test]
+    fn decode_relocations_from_dynamic() {
+        let class = ElfClass::Elf64;
+        let min_vaddr = 0x10000;
+        let mut image = vec![0u8; 0x100];
+        // 0x20处一项JUMP_SLOT的RELA，0x40处一项RELR
+        class.write_word(&mut image, 0x20, 0x10080).unwrap();
+        class
+            .write_word(&mut image, 0x28, 1 << 32 | R_AARCH64_JUMP_SLOT as u64)
+            .unwrap();
+        class.write_word(&mut image, 0x40, 0x10090).unwrap();
+
+        let dynamic = [
+            (DT_JMPREL, 0x10020),
+            (DT_PLTRELSZ, 0x18),
+            (DT_PLTREL, DT_RELA),
+            (DT_RELR, 0x10040),
+            (DT_RELRSZ, 8),
+        ]
+        .map(|(d_tag, d_val)| Dyn { d_tag, d_val });
+        let relocs = decode_relocations(&image, class, EM_AARCH64, min_vaddr, &dynamic).unwrap();
+
+        assert_eq!(relocs.len(), 2);
+        assert_eq!(relocs[0].format, RelocFormat::Rela);
+        assert_eq!(
+            (relocs[0].r_offset, relocs[0].r_type, relocs[0].r_sym),
+            (0x10080, R_AARCH64_JUMP_SLOT, 1)
+        );
+        assert!(is_got_type(EM_AARCH64, relocs[0].r_type));
+        assert_eq!(relocs[1].format, RelocFormat::Relr);
+        assert_eq!(
+            (relocs[1].r_offset, relocs[1].r_type),
+            (0x10090, R_AARCH64_RELATIVE)
+        );
+        assert!(decode_relocations(&image, class, 0, min_vaddr, &dynamic).is_err());
+    }
+
+    #[test]
+    fn got_types() {
+        assert!(is_got_type(EM_ARM, R_ARM_JUMP_SLOT));
+        assert!(is_got_type(EM_AARCH64, R_AARCH64_GLOB_DAT));
+        assert!(!is_got_type(EM_ARM, R_ARM_RELATIVE));
+    }
+}
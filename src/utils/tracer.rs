@@ -0,0 +1,656 @@
+use anyhow::{anyhow, Result};
+use goblin::elf::header::{EM_386, EM_AARCH64, EM_ARM, EM_X86_64};
+use nix::errno::Errno;
+use nix::libc;
+use nix::sys::ptrace::{self, Event, Options};
+use nix::sys::signal::Signal;
+use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
+use nix::unistd::Pid;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::os::unix::fs::FileExt;
+
+const PTRACE_GETREGSET: libc::c_int = 0x4204;
+const PTRACE_SETREGSET: libc::c_int = 0x4205;
+const PTRACE_EVENT_STOP: i32 = 128;
+const NT_PRSTATUS: libc::c_int = 1;
+
+/// 断点触发时机
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum BreakTrigger {
+    /// DT_INIT和.init_array中最后一个构造函数返回时
+    Init,
+    /// JNI_OnLoad返回时
+    JniOnload,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RegsLayout {
+    X86_64,
+    X86,
+    Arm64,
+    Arm,
+}
+
+/// PTRACE_GETREGSET(NT_PRSTATUS)读出的通用寄存器，按返回大小区分布局，兼容64位系统上的32位进程
+#[derive(Clone)]
+pub struct Registers {
+    data: [u8; 512],
+    len: usize,
+    layout: RegsLayout,
+}
+
+impl Registers {
+    fn word_size(&self) -> usize {
+        match self.layout {
+            RegsLayout::X86_64 | RegsLayout::Arm64 => 8,
+            RegsLayout::X86 | RegsLayout::Arm => 4,
+        }
+    }
+
+    fn get(&self, index: usize) -> u64 {
+        let size = self.word_size();
+        let mut word = [0u8; 8];
+        word[..size].copy_from_slice(&self.data[index * size..(index + 1) * size]);
+        u64::from_le_bytes(word)
+    }
+
+    fn set(&mut self, index: usize, value: u64) {
+        let size = self.word_size();
+        self.data[index * size..(index + 1) * size].copy_from_slice(&value.to_le_bytes()[..size]);
+    }
+
+    fn pc_index(&self) -> usize {
+        match self.layout {
+            RegsLayout::X86_64 => 16,
+            RegsLayout::X86 => 12,
+            RegsLayout::Arm64 => 32,
+            RegsLayout::Arm => 15,
+        }
+    }
+
+    fn sp_index(&self) -> usize {
+        match self.layout {
+            RegsLayout::X86_64 => 19,
+            RegsLayout::X86 => 15,
+            RegsLayout::Arm64 => 31,
+            RegsLayout::Arm => 13,
+        }
+    }
+
+    pub fn pc(&self) -> u64 {
+        self.get(self.pc_index())
+    }
+
+    pub fn set_pc(&mut self, value: u64) {
+        self.set(self.pc_index(), value);
+    }
+
+    pub fn sp(&self) -> u64 {
+        self.get(self.sp_index())
+    }
+
+    /// ARM的LR寄存器，x86的返回地址在栈上
+    pub fn link_register(&self) -> Option<u64> {
+        match self.layout {
+            RegsLayout::Arm64 => Some(self.get(30)),
+            RegsLayout::Arm => Some(self.get(14)),
+            RegsLayout::X86_64 | RegsLayout::X86 => None,
+        }
+    }
+
+    /// 系统调用入口处的调用号
+    pub fn syscall_number(&self) -> u64 {
+        match self.layout {
+            RegsLayout::X86_64 => self.get(15),
+            RegsLayout::X86 => self.get(11),
+            RegsLayout::Arm64 => self.get(8),
+            RegsLayout::Arm => self.get(7),
+        }
+    }
+
+    /// 系统调用入口处的6个参数
+    pub fn syscall_args(&self) -> [u64; 6] {
+        let indices = match self.layout {
+            RegsLayout::X86_64 => [14, 13, 12, 7, 9, 8],
+            RegsLayout::X86 | RegsLayout::Arm64 | RegsLayout::Arm => [0, 1, 2, 3, 4, 5],
+        };
+        indices.map(|index| self.get(index))
+    }
+
+    /// 系统调用出口处的返回值，32位进程按有符号扩展
+    pub fn syscall_result(&self) -> i64 {
+        let value = match self.layout {
+            RegsLayout::X86_64 => self.get(10),
+            RegsLayout::X86 => self.get(6),
+            RegsLayout::Arm64 | RegsLayout::Arm => self.get(0),
+        };
+        match self.word_size() {
+            8 => value as i64,
+            _ => value as u32 as i32 as i64,
+        }
+    }
+}
+
+/// 出口处的系统调用，参数在入口处记录
+#[derive(Debug, Clone, Copy)]
+pub struct Syscall {
+    pub number: u64,
+    pub args: [u64; 6],
+    pub result: i64,
+}
+
+impl Syscall {
+    pub fn failed(&self) -> bool {
+        (-4095..0).contains(&self.result)
+    }
+}
+
+/// 可能映射出可执行内存的系统调用
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapSyscall {
+    Mmap,
+    Mprotect,
+}
+
+impl MapSyscall {
+    /// 32位进程使用mmap2
+    pub fn from_number(machine: u16, number: u64) -> Option<Self> {
+        match (machine, number) {
+            (EM_X86_64, 9) | (EM_AARCH64, 222) | (EM_ARM | EM_386, 192) => Some(Self::Mmap),
+            (EM_X86_64, 10) | (EM_AARCH64, 226) | (EM_ARM | EM_386, 125) => Some(Self::Mprotect),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Mmap => "mmap",
+            Self::Mprotect => "mprotect",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TraceEvent {
+    Breakpoint { tid: Pid, address: u64 },
+    Syscall { tid: Pid, call: Syscall },
+    Exited,
+}
+
+/// 附加目标进程的所有线程（含之后创建的线程），管理软件断点和系统调用跟踪
+pub struct Tracer {
+    pid: u32,
+    machine: u16,
+    mem: File,
+    threads: HashSet<Pid>,
+    running: HashSet<Pid>,
+    interrupted: HashSet<Pid>,
+    pending: VecDeque<WaitStatus>,
+    breakpoints: HashMap<u64, Vec<u8>>,
+    retired: HashSet<u64>,
+    syscalls: bool,
+    entries: HashMap<Pid, (u64, [u64; 6])>,
+}
+
+fn list_threads(pid: u32) -> Result<Vec<Pid>> {
+    Ok(fs::read_dir(format!("/proc/{}/task", pid))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+        .map(Pid::from_raw)
+        .collect())
+}
+
+fn is_interrupt_stop(status: &WaitStatus) -> bool {
+    matches!(status, WaitStatus::PtraceEvent(_, _, event) if *event == PTRACE_EVENT_STOP)
+}
+
+impl Tracer {
+    /// PTRACE_SEIZE所有线程并中断，附加期间新建的线程通过PTRACE_O_TRACECLONE跟踪
+    /// 返回时所有线程处于停止状态，设置好断点后调用resume_all
+    pub fn attach(pid: u32, machine: u16) -> Result<Self> {
+        let mem = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(format!("/proc/{}/mem", pid))
+            .map_err(|e| anyhow!("Failed to open /proc/{}/mem for writing: {}", pid, e))?;
+        let mut tracer = Self {
+            pid,
+            machine,
+            mem,
+            threads: HashSet::new(),
+            running: HashSet::new(),
+            interrupted: HashSet::new(),
+            pending: VecDeque::new(),
+            breakpoints: HashMap::new(),
+            retired: HashSet::new(),
+            syscalls: false,
+            entries: HashMap::new(),
+        };
+
+        loop {
+            let new: Vec<Pid> = list_threads(pid)?
+                .into_iter()
+                .filter(|tid| !tracer.threads.contains(tid))
+                .collect();
+            if new.is_empty() {
+                break;
+            }
+            for tid in new {
+                match ptrace::seize(tid, Options::PTRACE_O_TRACECLONE) {
+                    Ok(()) => {}
+                    // 已通过clone事件自动附加
+                    Err(Errno::EPERM) if !tracer.threads.is_empty() => {}
+                    Err(e) => return Err(anyhow!("ptrace seize {} failed: {}", tid, e)),
+                }
+                tracer.threads.insert(tid);
+                tracer.running.insert(tid);
+            }
+            tracer.stop_all(None)?;
+        }
+
+        Ok(tracer)
+    }
+
+    /// 对所有线程设置PTRACE_O_TRACESYSGOOD，之后以PTRACE_SYSCALL继续运行
+    /// 需在attach之后、resume_all之前调用，此时所有线程都不在系统调用中
+    pub fn trace_syscalls(&mut self) -> Result<()> {
+        let options = Options::PTRACE_O_TRACECLONE | Options::PTRACE_O_TRACESYSGOOD;
+        for tid in self.threads.iter().copied().collect::<Vec<_>>() {
+            match ptrace::setoptions(tid, options) {
+                Ok(()) => {}
+                Err(Errno::ESRCH) => self.forget(tid),
+                Err(e) => return Err(anyhow!("ptrace setoptions {} failed: {}", tid, e)),
+            }
+        }
+        self.syscalls = true;
+        Ok(())
+    }
+
+    pub fn thread_count(&self) -> usize {
+        self.threads.len()
+    }
+
+    pub fn read_memory(&self, address: u64, size: usize) -> Result<Vec<u8>> {
+        let mut buffer = vec![0u8; size];
+        self.mem.read_exact_at(&mut buffer, address)?;
+        Ok(buffer)
+    }
+
+    fn write_memory(&self, address: u64, data: &[u8]) -> Result<()> {
+        self.mem
+            .write_all_at(data, address)
+            .map_err(|e| anyhow!("Failed to write {:#x}: {}", address, e))
+    }
+
+    fn breakpoint_insn(&self, thumb: bool) -> Result<&'static [u8]> {
+        match self.machine {
+            EM_X86_64 | EM_386 => Ok(&[0xcc]),
+            EM_AARCH64 => Ok(&[0x00, 0x00, 0x20, 0xd4]),
+            EM_ARM if thumb => Ok(&[0x01, 0xde]),
+            EM_ARM => Ok(&[0xf0, 0x01, 0xf0, 0xe7]),
+            other => Err(anyhow!("Unsupported machine {} for breakpoints", other)),
+        }
+    }
+
+    /// 写入断点，ARM地址最低位为1时使用Thumb断点，返回断点地址
+    pub fn insert_breakpoint(&mut self, address: u64) -> Result<u64> {
+        let thumb = self.machine == EM_ARM && address & 1 != 0;
+        let address = if self.machine == EM_ARM {
+            address & !1
+        } else {
+            address
+        };
+        if self.breakpoints.contains_key(&address) {
+            return Ok(address);
+        }
+
+        let insn = self.breakpoint_insn(thumb)?;
+        let original = self.read_memory(address, insn.len())?;
+        self.write_memory(address, insn)?;
+        self.breakpoints.insert(address, original);
+        self.retired.remove(&address);
+        Ok(address)
+    }
+
+    pub fn remove_breakpoint(&mut self, address: u64) -> Result<()> {
+        if let Some(original) = self.breakpoints.remove(&address) {
+            self.write_memory(address, &original)?;
+            self.retired.insert(address);
+        }
+        Ok(())
+    }
+
+    pub fn get_regs(&self, tid: Pid) -> Result<Registers> {
+        let mut data = [0u8; 512];
+        let mut iov = libc::iovec {
+            iov_base: data.as_mut_ptr() as *mut libc::c_void,
+            iov_len: data.len(),
+        };
+        let ret = unsafe {
+            libc::ptrace(
+                PTRACE_GETREGSET as _,
+                tid.as_raw(),
+                NT_PRSTATUS as usize as *mut libc::c_void,
+                &mut iov as *mut libc::iovec as *mut libc::c_void,
+            )
+        };
+        Errno::result(ret).map_err(|e| anyhow!("PTRACE_GETREGSET {} failed: {}", tid, e))?;
+
+        let layout = match iov.iov_len {
+            216 => RegsLayout::X86_64,
+            68 => RegsLayout::X86,
+            272 => RegsLayout::Arm64,
+            72 => RegsLayout::Arm,
+            other => return Err(anyhow!("Unknown register set size {}", other)),
+        };
+        Ok(Registers {
+            data,
+            len: iov.iov_len,
+            layout,
+        })
+    }
+
+    pub fn set_regs(&self, tid: Pid, regs: &Registers) -> Result<()> {
+        let mut data = regs.data;
+        let mut iov = libc::iovec {
+            iov_base: data.as_mut_ptr() as *mut libc::c_void,
+            iov_len: regs.len,
+        };
+        let ret = unsafe {
+            libc::ptrace(
+                PTRACE_SETREGSET as _,
+                tid.as_raw(),
+                NT_PRSTATUS as usize as *mut libc::c_void,
+                &mut iov as *mut libc::iovec as *mut libc::c_void,
+            )
+        };
+        Errno::result(ret).map_err(|e| anyhow!("PTRACE_SETREGSET {} failed: {}", tid, e))?;
+        Ok(())
+    }
+
+    /// 在函数入口处读取返回地址
+    pub fn return_address(&self, tid: Pid) -> Result<u64> {
+        let regs = self.get_regs(tid)?;
+        if let Some(lr) = regs.link_register() {
+            return Ok(lr);
+        }
+        let data = self.read_memory(regs.sp(), regs.word_size())?;
+        Ok(match regs.word_size() {
+            8 => u64::from_le_bytes(data[..8].try_into()?),
+            _ => u32::from_le_bytes(data[..4].try_into()?) as u64,
+        })
+    }
+
+    /// 在返回地址处判断entry_sp记录的栈帧是否已经返回，嵌套调用返回时栈更深
+    /// x86的ret弹出返回地址后sp大于入口处，ARM返回后sp与入口处相同
+    pub fn frame_returned(&self, tid: Pid, entry_sp: u64) -> Result<bool> {
+        let sp = self.get_regs(tid)?.sp();
+        Ok(match self.machine {
+            EM_X86_64 | EM_386 => sp > entry_sp,
+            _ => sp >= entry_sp,
+        })
+    }
+
+    /// 单步执行停止线程的当前指令，用于越过临时移除的断点，调用前其他线程应已停止
+    /// 单步期间收到的信号随下一次单步注入，信号处理函数返回后可能再次命中断点
+    pub fn single_step(&mut self, tid: Pid) -> Result<()> {
+        let mut signal = None;
+        loop {
+            match ptrace::step(tid, signal) {
+                Ok(()) => {}
+                Err(Errno::ESRCH) => {
+                    self.forget(tid);
+                    return Ok(());
+                }
+                Err(e) => return Err(anyhow!("ptrace singlestep {} failed: {}", tid, e)),
+            }
+            signal = None;
+            match waitpid(tid, Some(WaitPidFlag::__WALL)) {
+                Ok(WaitStatus::Stopped(_, Signal::SIGTRAP)) => return Ok(()),
+                Ok(WaitStatus::Stopped(_, other)) => signal = Some(other),
+                Ok(WaitStatus::PtraceEvent(_, _, event))
+                    if event == Event::PTRACE_EVENT_CLONE as i32 =>
+                {
+                    self.track_clone(tid)
+                }
+                Ok(WaitStatus::Exited(..)) | Ok(WaitStatus::Signaled(..)) | Err(_) => {
+                    self.forget(tid);
+                    return Ok(());
+                }
+                Ok(_) => {}
+            }
+        }
+    }
+
+    // x86的int3执行后PC指向下一字节
+    fn trap_address(&self, regs: &Registers) -> u64 {
+        match self.machine {
+            EM_X86_64 | EM_386 => regs.pc().wrapping_sub(1),
+            _ => regs.pc(),
+        }
+    }
+
+    // 包括已移除的断点，其他线程可能在移除前已经命中
+    fn breakpoint_trap(&self, tid: Pid) -> Option<(u64, Registers)> {
+        let regs = self.get_regs(tid).ok()?;
+        let address = self.trap_address(&regs);
+        (self.breakpoints.contains_key(&address) || self.retired.contains(&address))
+            .then_some((address, regs))
+    }
+
+    /// 继续运行停止的线程
+    pub fn resume(&mut self, tid: Pid) -> Result<()> {
+        self.resume_with(tid, None)
+    }
+
+    fn resume_with(&mut self, tid: Pid, signal: Option<Signal>) -> Result<()> {
+        let result = if self.syscalls {
+            ptrace::syscall(tid, signal)
+        } else {
+            ptrace::cont(tid, signal)
+        };
+        match result {
+            Ok(()) => {
+                self.running.insert(tid);
+                Ok(())
+            }
+            Err(Errno::ESRCH) => {
+                self.forget(tid);
+                Ok(())
+            }
+            Err(e) => Err(anyhow!("ptrace cont {} failed: {}", tid, e)),
+        }
+    }
+
+    fn forget(&mut self, tid: Pid) {
+        self.threads.remove(&tid);
+        self.running.remove(&tid);
+        self.interrupted.remove(&tid);
+        self.entries.remove(&tid);
+    }
+
+    // 记录clone事件中新建的线程，新线程以PTRACE_EVENT_STOP开始
+    fn track_clone(&mut self, tid: Pid) {
+        if let Ok(new) = ptrace::getevent(tid) {
+            let new = Pid::from_raw(new as i32);
+            self.threads.insert(new);
+            self.running.insert(new);
+        }
+    }
+
+    /// 中断除except外所有运行中的线程，其间收到的其他事件留待wait_event处理
+    pub fn stop_all(&mut self, except: Option<Pid>) -> Result<()> {
+        loop {
+            let targets: Vec<Pid> = self
+                .running
+                .iter()
+                .copied()
+                .filter(|tid| Some(*tid) != except)
+                .collect();
+            if targets.is_empty() {
+                return Ok(());
+            }
+
+            for tid in targets {
+                if ptrace::interrupt(tid).is_err() {
+                    self.forget(tid);
+                    continue;
+                }
+                let status = match waitpid(tid, Some(WaitPidFlag::__WALL)) {
+                    Ok(status) => status,
+                    Err(_) => {
+                        self.forget(tid);
+                        continue;
+                    }
+                };
+                self.running.remove(&tid);
+                match status {
+                    WaitStatus::Exited(..) | WaitStatus::Signaled(..) => self.forget(tid),
+                    status if is_interrupt_stop(&status) => {
+                        self.interrupted.insert(tid);
+                    }
+                    WaitStatus::PtraceEvent(_, _, event)
+                        if event == Event::PTRACE_EVENT_CLONE as i32 =>
+                    {
+                        self.track_clone(tid);
+                        self.interrupted.insert(tid);
+                    }
+                    status => self.pending.push_back(status),
+                }
+            }
+        }
+    }
+
+    /// 恢复stop_all中断的线程
+    pub fn resume_all(&mut self) -> Result<()> {
+        for tid in self.interrupted.drain().collect::<Vec<_>>() {
+            self.resume_with(tid, None)?;
+        }
+        Ok(())
+    }
+
+    /// 等待下一个断点或系统调用出口事件，其他停止事件在内部处理并继续运行
+    pub fn wait_event(&mut self) -> Result<TraceEvent> {
+        loop {
+            if self.threads.is_empty() {
+                return Ok(TraceEvent::Exited);
+            }
+            let status = match self.pending.pop_front() {
+                Some(status) => status,
+                None => match waitpid(None, Some(WaitPidFlag::__WALL)) {
+                    Ok(status) => status,
+                    Err(Errno::ECHILD) => return Ok(TraceEvent::Exited),
+                    Err(e) => return Err(anyhow!("waitpid failed: {}", e)),
+                },
+            };
+            let Some(tid) = status.pid() else {
+                continue;
+            };
+            self.running.remove(&tid);
+
+            match status {
+                WaitStatus::Exited(..) | WaitStatus::Signaled(..) => self.forget(tid),
+                WaitStatus::PtraceEvent(_, _, event)
+                    if event == Event::PTRACE_EVENT_CLONE as i32 =>
+                {
+                    self.track_clone(tid);
+                    self.resume(tid)?;
+                }
+                // 中断、新线程或group-stop
+                status if is_interrupt_stop(&status) => {
+                    self.threads.insert(tid);
+                    self.resume(tid)?;
+                }
+                // 入口处记录调用号和参数，出口处连同返回值一起返回
+                WaitStatus::PtraceSyscall(_) => {
+                    let regs = match self.get_regs(tid) {
+                        Ok(regs) => regs,
+                        Err(_) => {
+                            self.forget(tid);
+                            continue;
+                        }
+                    };
+                    match self.entries.remove(&tid) {
+                        Some((number, args)) => {
+                            let call = Syscall {
+                                number,
+                                args,
+                                result: regs.syscall_result(),
+                            };
+                            return Ok(TraceEvent::Syscall { tid, call });
+                        }
+                        None => {
+                            self.entries
+                                .insert(tid, (regs.syscall_number(), regs.syscall_args()));
+                            self.resume(tid)?;
+                        }
+                    }
+                }
+                WaitStatus::Stopped(_, Signal::SIGTRAP) => match self.breakpoint_trap(tid) {
+                    Some((address, mut regs)) => {
+                        regs.set_pc(address);
+                        self.set_regs(tid, &regs)?;
+                        if self.breakpoints.contains_key(&address) {
+                            return Ok(TraceEvent::Breakpoint { tid, address });
+                        }
+                        self.resume(tid)?;
+                    }
+                    None => self.resume_with(tid, Some(Signal::SIGTRAP))?,
+                },
+                WaitStatus::Stopped(_, signal) => self.resume_with(tid, Some(signal))?,
+                _ => self.resume(tid)?,
+            }
+        }
+    }
+
+    /// 移除所有断点并分离所有线程
+    pub fn detach(&mut self) -> Result<()> {
+        if self.threads.is_empty() {
+            return Ok(());
+        }
+        self.stop_all(None)?;
+
+        let mut signals = HashMap::new();
+        while let Some(status) = self.pending.pop_front() {
+            match status {
+                // 已命中但未处理的断点需要回退PC
+                WaitStatus::Stopped(tid, Signal::SIGTRAP) => {
+                    if let Some((address, mut regs)) = self.breakpoint_trap(tid) {
+                        regs.set_pc(address);
+                        self.set_regs(tid, &regs)?;
+                    } else {
+                        signals.insert(tid, Signal::SIGTRAP);
+                    }
+                }
+                WaitStatus::Stopped(tid, signal) => {
+                    signals.insert(tid, signal);
+                }
+                _ => {}
+            }
+        }
+
+        for address in self.breakpoints.keys().copied().collect::<Vec<_>>() {
+            self.remove_breakpoint(address)?;
+        }
+        // 先脱离的线程可能让进程退出，主线程最后脱离，脱离失败的线程正在退出，需要回收
+        let mut threads: Vec<Pid> = self.threads.drain().collect();
+        threads.sort_by_key(|tid| tid.as_raw() as u32 == self.pid);
+        for tid in threads {
+            if ptrace::detach(tid, signals.remove(&tid)).is_err() {
+                let _ = waitpid(tid, Some(WaitPidFlag::__WALL));
+            }
+        }
+        self.running.clear();
+        self.interrupted.clear();
+        self.entries.clear();
+        println!("[+] Detached from {}", self.pid);
+        Ok(())
+    }
+}
+
+impl Drop for Tracer {
+    fn drop(&mut self) {
+        let _ = self.detach();
+    }
+}
@@ -15,7 +15,27 @@ pub struct MemoryMapping {
 
 #[derive(Debug)]
 pub struct SoInfo {
+    pub phdr: u64,
+    pub phnum: u64,
     pub base: u64,
     pub size: u64,
+    pub dynamic: u64,
     pub next: u64,
+    pub flags: u32,
+    pub strtab: u64,
+    pub symtab: u64,
+    pub init_array: u64,
+    pub init_array_count: u64,
+    pub fini_array: u64,
+    pub fini_array_count: u64,
+    pub constructors_called: bool,
+    pub load_bias: u64,
+}
+
+/// bionic的android_namespace_t，members为soinfo地址
+#[derive(Debug)]
+pub struct LinkerNamespace {
+    pub address: u64,
+    pub name: String,
+    pub members: Vec<u64>,
 }
@@ -0,0 +1,11 @@
+// 用于SoFixer测试的最小SO，libfixture_x86_64_dump.bin由以下步骤生成：
+// gcc -shared -fPIC -O1 -nostdlib -Wl,--hash-style=both -Wl,-z,max-page-size=0x1000 \
+//     -Wl,--build-id -o libfixture.so fixture.c
+// x86_64 glibc进程dlopen后从内存dump得到，加载基址0x7f641345c000
+int fixture_counter = 1;
+static int (*fixture_callback)(int);
+
+int fixture_add(int a, int b) { return a + b + fixture_counter; }
+int fixture_twice(int a) { return a * 2; }
+void fixture_set(int (*cb)(int)) { fixture_callback = cb ? cb : fixture_twice; }
+int (*fixture_default)(int) = fixture_twice;
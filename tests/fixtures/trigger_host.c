@@ -0,0 +1,33 @@
+// dump_on_trigger测试用宿主进程，另起一个线程后等待附加，再dlopen argv[1]并调用JNI_OnLoad
+#include <dlfcn.h>
+#include <pthread.h>
+#include <stdio.h>
+#include <unistd.h>
+
+static void *spin(void *arg) {
+    (void)arg;
+    for (;;) {
+        usleep(1000);
+    }
+    return NULL;
+}
+
+int main(int argc, char **argv) {
+    if (argc < 2) {
+        return 2;
+    }
+    pthread_t thread;
+    pthread_create(&thread, NULL, spin, NULL);
+    sleep(1);
+
+    void *handle = dlopen(argv[1], RTLD_NOW);
+    if (handle == NULL) {
+        fprintf(stderr, "%s\n", dlerror());
+        return 1;
+    }
+    int (*onload)(void *, void *) = (int (*)(void *, void *))dlsym(handle, "JNI_OnLoad");
+    if (onload == NULL || onload(NULL, NULL) != 0x10006) {
+        return 1;
+    }
+    return 0;
+}
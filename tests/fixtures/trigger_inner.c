@@ -0,0 +1,6 @@
+// 由trigger_lib.c的构造函数加载
+int inner_counter;
+
+static void __attribute__((constructor)) inner_init(void) {
+    inner_counter++;
+}
@@ -0,0 +1,26 @@
+// dump_on_trigger测试用SO，构造函数中先dlopen另一个SO再还原标记，JNI_OnLoad清除标记
+// 内层SO的构造函数与本SO的构造函数返回到linker中同一地址，用于检查嵌套调用不会提前触发dump
+#include <dlfcn.h>
+#include <stdlib.h>
+#include <string.h>
+
+static const char reversed[] = "KO-REGGIRT-PMUDYNIT";
+static char marker[32] = "pending";
+
+static void __attribute__((constructor)) restore_marker(void) {
+    const char *inner = getenv("TRIGGER_INNER");
+    if (inner != NULL && dlopen(inner, RTLD_NOW) == NULL) {
+        abort();
+    }
+    size_t len = strlen(reversed);
+    for (size_t i = 0; i < len; i++) {
+        marker[i] = reversed[len - 1 - i];
+    }
+}
+
+int JNI_OnLoad(void *vm, void *reserved) {
+    (void)vm;
+    (void)reserved;
+    memset(marker, 0, sizeof(marker));
+    return 0x10006;
+}